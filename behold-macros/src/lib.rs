@@ -0,0 +1,51 @@
+//! # Behold Macros
+//! Procedural macros for `behold`, re-exported by it when the `macros` feature is enabled.
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, ImplItem, ItemImpl, Type};
+
+/// Trace entry to and exit from every method in an `impl` block, tagged with the type name.
+///
+/// Each method starts by calling `behold().tag(TypeName).trace_call(method_name)`, so the
+/// usual conditionals such as tag filters apply. `const fn`s are left alone.
+#[proc_macro_attribute]
+pub fn trace_impl(args: TokenStream, input: TokenStream) -> TokenStream {
+    if let Some(token) = proc_macro2::TokenStream::from(args).into_iter().next() {
+        return syn::Error::new(token.span(), "trace_impl takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+    let mut item = parse_macro_input!(input as ItemImpl);
+    let tag = type_name(&item.self_ty);
+    for impl_item in &mut item.items {
+        if let ImplItem::Fn(ref mut method) = *impl_item {
+            if method.sig.constness.is_some() {
+                continue;
+            }
+            let name = method.sig.ident.to_string();
+            let trace = syn::parse_quote! {
+                let _behold_trace = ::behold::behold().tag(#tag).trace_call(#name);
+            };
+            method.block.stmts.insert(0, trace);
+        }
+    }
+    quote!(#item).into()
+}
+
+/// The name of the type an `impl` block is for, without any generic arguments
+fn type_name(ty: &Type) -> String {
+    match *ty {
+        Type::Path(ref path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default(),
+        ref other => quote!(#other).to_string().replace(' ', ""),
+    }
+}
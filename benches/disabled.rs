@@ -0,0 +1,59 @@
+//! Benchmarks for statements which produce no output, which should cost next to nothing.
+#[macro_use]
+extern crate criterion;
+extern crate behold;
+
+use behold::{behold, Behold};
+use criterion::{black_box, Criterion};
+
+fn when_false(c: &mut Criterion) {
+    c.bench_function("when(false).show", |b| {
+        b.iter(|| {
+            behold().when(black_box(false)).show(String::new());
+        })
+    });
+}
+
+fn missing_context_key(c: &mut Criterion) {
+    behold().set_context("enabled", true);
+    c.bench_function("when_context(missing).show", |b| {
+        b.iter(|| {
+            behold()
+                .when_context(black_box("missing"))
+                .show(String::new());
+        })
+    });
+}
+
+fn disabled_context_key(c: &mut Criterion) {
+    behold().set_context("disabled", false);
+    c.bench_function("when_context(disabled).show", |b| {
+        b.iter(|| {
+            behold()
+                .when_context(black_box("disabled"))
+                .show(String::new());
+        })
+    });
+}
+
+fn disable_all(c: &mut Criterion) {
+    behold().set_context("enabled", true);
+    Behold::disable_all();
+    c.bench_function("disable_all + when_context(enabled).show", |b| {
+        b.iter(|| {
+            behold()
+                .when_context(black_box("enabled"))
+                .show(String::new());
+        })
+    });
+    Behold::enable_all();
+}
+
+criterion_group!(
+    benches,
+    when_false,
+    missing_context_key,
+    disabled_context_key,
+    disable_all
+);
+criterion_main!(benches);
@@ -0,0 +1,128 @@
+//! Counting of the allocations made on each thread, via a wrapper around the global allocator.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::LocalKey;
+
+use Behold;
+
+/// A global allocator which counts the allocations made on each thread, for use with
+/// `count_allocs`, before passing them on to another allocator
+/// # Examples
+/// ```
+/// use std::alloc::System;
+/// use behold::CountingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator<System> = CountingAllocator::new(System);
+/// # fn main() {}
+/// ```
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+impl<A> CountingAllocator<A> {
+    /// Count the allocations made, passing them on to the provided allocator
+    pub const fn new(inner: A) -> Self {
+        CountingAllocator { inner }
+    }
+}
+
+/// Whether a `CountingAllocator` has been used, so reports can tell zero from uncounted
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Const initialized with no destructor, so these can be read from within the allocator
+    static COUNT: Cell<u64> = const { Cell::new(0) };
+    static REALLOCS: Cell<u64> = const { Cell::new(0) };
+    static BYTES: Cell<u64> = const { Cell::new(0) };
+}
+
+fn count(counter: &'static LocalKey<Cell<u64>>, size: usize) {
+    INSTALLED.store(true, Ordering::Relaxed);
+    let _ = counter.try_with(|count| count.set(count.get() + 1));
+    let _ = BYTES.try_with(|bytes| bytes.set(bytes.get() + size as u64));
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(&COUNT, layout.size());
+        self.inner.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(&COUNT, layout.size());
+        self.inner.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // Only growth requests more memory - the original allocation was counted already
+        count(&REALLOCS, new_size.saturating_sub(layout.size()));
+        self.inner.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout)
+    }
+}
+
+/// Allocations and reallocations made on this thread so far, and the bytes they requested
+fn totals() -> (u64, u64, u64) {
+    (
+        COUNT.try_with(Cell::get).unwrap_or_default(),
+        REALLOCS.try_with(Cell::get).unwrap_or_default(),
+        BYTES.try_with(Cell::get).unwrap_or_default(),
+    )
+}
+
+/// Guard returned by `count_allocs` - when dropped, shows how many allocations were made on
+/// this thread while it was alive
+#[must_use = "counting stops as soon as the guard is dropped"]
+pub struct AllocCount {
+    /// The instance which started counting, or None if it wasn't speaking up
+    behold: Option<Behold>,
+    label: String,
+    /// Totals when counting started
+    started: (u64, u64, u64),
+}
+
+impl AllocCount {
+    pub(crate) fn start(behold: Option<Behold>, label: &str) -> Self {
+        AllocCount {
+            behold,
+            label: label.to_string(),
+            started: totals(),
+        }
+    }
+}
+
+impl Drop for AllocCount {
+    fn drop(&mut self) {
+        if let Some(behold) = self.behold.take() {
+            let (count, reallocs, bytes) = totals();
+            let msg = if INSTALLED.load(Ordering::Relaxed) {
+                let mut allocations = match count - self.started.0 {
+                    1 => "1 allocation".to_string(),
+                    n => format!("{} allocations", n),
+                };
+                match reallocs - self.started.1 {
+                    0 => {}
+                    1 => allocations.push_str(", 1 reallocation"),
+                    n => allocations.push_str(&format!(", {} reallocations", n)),
+                }
+                format!(
+                    "{}: {}, {} bytes",
+                    self.label,
+                    allocations,
+                    bytes - self.started.2
+                )
+            } else {
+                format!(
+                    "{}: allocations not counted - CountingAllocator isn't the global allocator",
+                    self.label
+                )
+            };
+            behold.show(msg);
+        }
+    }
+}
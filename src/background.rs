@@ -0,0 +1,188 @@
+//! A writer thread which takes output off the threads producing it.
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use output::{self, Pending};
+
+/// Whether output is being handed to the writer thread
+static RUNNING: AtomicBool = AtomicBool::new(false);
+/// Whether the writer thread has been spawned - it keeps running once it has
+static SPAWNED: AtomicBool = AtomicBool::new(false);
+/// How many messages have been dropped because the queue was full
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Determines what happens to output shown while the background writer's queue is full
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Drop the output being shown, keeping what's already queued
+    #[default]
+    DropNewest,
+    /// Drop the oldest queued output to make room
+    DropOldest,
+    /// Wait for the writer to make room, as if writing the output directly
+    Block,
+}
+
+lazy_static! {
+    static ref QUEUE: Queue = Queue {
+        state: Mutex::new(State {
+            pending: VecDeque::new(),
+            capacity: 0,
+            overflow: Overflow::DropNewest,
+            writing: false,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        idle: Condvar::new(),
+    };
+}
+
+/// Output waiting for the writer thread, bounded so that a slow destination can't use up memory
+struct Queue {
+    state: Mutex<State>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    /// Notified whenever the writer finishes with the queue empty
+    idle: Condvar,
+}
+
+struct State {
+    pending: VecDeque<Pending>,
+    capacity: usize,
+    overflow: Overflow,
+    /// Whether the writer is writing output it has taken off the queue
+    writing: bool,
+}
+
+/// Hand output to the writer thread, spawning it if need be, queueing up to `capacity` pieces of output
+pub(crate) fn start(capacity: usize) -> io::Result<()> {
+    match QUEUE.state.lock() {
+        Ok(mut state) => state.capacity = capacity.max(1),
+        Err(err) => panic!(
+            "write_in_background called - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+    if !SPAWNED.swap(true, Ordering::AcqRel) {
+        let spawned = thread::Builder::new()
+            .name("behold-writer".to_string())
+            .spawn(run);
+        if let Err(err) = spawned {
+            SPAWNED.store(false, Ordering::Release);
+            return Err(err);
+        }
+    }
+    RUNNING.store(true, Ordering::Release);
+    Ok(())
+}
+
+pub(crate) fn is_running() -> bool {
+    RUNNING.load(Ordering::Acquire)
+}
+
+pub(crate) fn set_overflow(overflow: Overflow) {
+    match QUEUE.state.lock() {
+        Ok(mut state) => state.overflow = overflow,
+        Err(err) => panic!(
+            "set_overflow_policy called on an instance of Behold - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+    // Anyone waiting for room should apply the new policy instead
+    QUEUE.not_full.notify_all();
+}
+
+#[cfg(feature = "status-endpoint")]
+pub(crate) fn overflow() -> Overflow {
+    QUEUE
+        .state
+        .lock()
+        .map(|state| state.overflow)
+        .unwrap_or_default()
+}
+
+pub(crate) fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Queue output for the writer thread, applying the overflow policy if the queue is full
+pub(crate) fn push(pending: Pending) {
+    let mut state = match QUEUE.state.lock() {
+        Ok(state) => state,
+        // Output is best-effort; don't take the producing thread down with the writer
+        Err(_) => return,
+    };
+    while state.pending.len() >= state.capacity {
+        match state.overflow {
+            Overflow::DropNewest => {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            Overflow::DropOldest => {
+                state.pending.pop_front();
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+            }
+            Overflow::Block => {
+                state = match QUEUE.not_full.wait(state) {
+                    Ok(state) => state,
+                    Err(_) => return,
+                };
+            }
+        }
+    }
+    state.pending.push_back(pending);
+    QUEUE.not_empty.notify_one();
+}
+
+/// Stop handing output to the writer thread, and wait for it to write everything queued so far
+pub(crate) fn drain() {
+    if !RUNNING.swap(false, Ordering::AcqRel) {
+        return;
+    }
+    let mut state = match QUEUE.state.lock() {
+        Ok(state) => state,
+        Err(_) => return,
+    };
+    // Nothing more will be queued, so let anyone waiting for room in
+    state.capacity = usize::MAX;
+    QUEUE.not_full.notify_all();
+    while state.writing || !state.pending.is_empty() {
+        state = match QUEUE.idle.wait(state) {
+            Ok(state) => state,
+            Err(_) => return,
+        };
+    }
+}
+
+/// Write queued output until the process exits
+fn run() {
+    let mut state = match QUEUE.state.lock() {
+        Ok(state) => state,
+        Err(_) => return,
+    };
+    loop {
+        match state.pending.pop_front() {
+            Some(pending) => {
+                state.writing = true;
+                QUEUE.not_full.notify_one();
+                drop(state);
+                output::deliver(pending);
+                state = match QUEUE.state.lock() {
+                    Ok(state) => state,
+                    Err(_) => return,
+                };
+                state.writing = false;
+            }
+            None => {
+                QUEUE.idle.notify_all();
+                state = match QUEUE.not_empty.wait(state) {
+                    Ok(state) => state,
+                    Err(_) => return,
+                };
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! A banner describing the build, shown ahead of the first output.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+lazy_static! {
+    /// The banner's description of the build, until it's shown
+    static ref BANNER: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// Whether a banner is waiting to be shown, so that output needn't lock otherwise
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Show a banner describing the build ahead of the next output
+pub(crate) fn set(name: &str, version: &str, commit: Option<&str>) {
+    let mut banner = format!("{} {}", name, version);
+    if let Some(commit) = commit {
+        banner.push_str(&format!(" ({})", commit));
+    }
+    match BANNER.lock() {
+        Ok(mut current) => *current = Some(banner),
+        Err(err) => panic!(
+            "show_banner called on an instance of Behold - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+    PENDING.store(true, Ordering::Release);
+}
+
+/// Take the banner if it hasn't been shown yet
+pub(crate) fn take() -> Option<String> {
+    if !PENDING.swap(false, Ordering::AcqRel) {
+        return None;
+    }
+    BANNER.lock().ok().and_then(|mut banner| banner.take())
+}
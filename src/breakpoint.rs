@@ -0,0 +1,35 @@
+//! Trapping into an attached debugger.
+
+/// Trap into the debugger with the architecture's breakpoint instruction
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub(crate) fn trap() {
+    unsafe { std::arch::asm!("int3", options(nomem, nostack)) }
+}
+
+/// Trap into the debugger with the architecture's breakpoint instruction, using the immediate
+/// lldb and gdb expect
+#[cfg(target_arch = "aarch64")]
+pub(crate) fn trap() {
+    unsafe { std::arch::asm!("brk #0xf000", options(nomem, nostack)) }
+}
+
+/// Trap into the debugger by raising SIGTRAP, on architectures without a breakpoint
+/// instruction above
+#[cfg(all(
+    unix,
+    not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64"))
+))]
+pub(crate) fn trap() {
+    unsafe {
+        libc::raise(libc::SIGTRAP);
+    }
+}
+
+/// There's no way to trap into the debugger here, so carry on
+#[cfg(not(any(
+    unix,
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "aarch64"
+)))]
+pub(crate) fn trap() {}
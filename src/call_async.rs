@@ -0,0 +1,30 @@
+//! Conditionally awaiting diagnostic work.
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Future returned by `call_async` - completes immediately if the Behold instance was silent
+#[must_use = "futures do nothing unless awaited or polled"]
+pub struct CallAsync<F> {
+    /// The diagnostic work, only created if the Behold instance was speaking up
+    future: Option<Pin<Box<F>>>,
+}
+
+impl<F: Future<Output = ()>> CallAsync<F> {
+    pub(crate) fn new(future: Option<F>) -> Self {
+        CallAsync {
+            future: future.map(Box::pin),
+        }
+    }
+}
+
+impl<F: Future<Output = ()>> Future for CallAsync<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        match self.future {
+            Some(ref mut future) => future.as_mut().poll(cx),
+            None => Poll::Ready(()),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Redirection of the process's stdout and stderr, so that noisy code can be contained.
+use std::cell::Cell;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use libc::{c_int, STDERR_FILENO, STDOUT_FILENO};
+
+lazy_static! {
+    /// Held while output is redirected, since the file descriptors are shared by every thread
+    static ref CAPTURING: Mutex<()> = Mutex::new(());
+}
+
+/// Distinguishes the files of captures made by this process
+static CAPTURES: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// Whether this thread is already capturing, so that nested captures don't deadlock
+    static NESTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Points stdout and stderr at a file until dropped
+struct Redirect {
+    file: File,
+    /// Duplicates of the original stdout and stderr, or -1 if not yet duplicated
+    saved: [c_int; 2],
+}
+
+impl Redirect {
+    fn start() -> io::Result<Self> {
+        let path = env::temp_dir().join(format!(
+            "behold-capture-{}-{}",
+            process::id(),
+            CAPTURES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        // The file stays readable through its handle once unlinked
+        fs::remove_file(&path)?;
+        let _ = io::stdout().flush();
+        let mut redirect = Redirect {
+            file,
+            saved: [-1, -1],
+        };
+        for (saved, fd) in redirect
+            .saved
+            .iter_mut()
+            .zip([STDOUT_FILENO, STDERR_FILENO].iter())
+        {
+            let duplicate = unsafe { libc::dup(*fd) };
+            if duplicate < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            *saved = duplicate;
+            if unsafe { libc::dup2(redirect.file.as_raw_fd(), *fd) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(redirect)
+    }
+
+    /// Everything written to stdout and stderr so far
+    fn read(&mut self) -> String {
+        let _ = io::stdout().flush();
+        let mut bytes = Vec::new();
+        let _ = self
+            .file
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.file.read_to_end(&mut bytes));
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        // A panic message would otherwise vanish along with the rest of the output
+        let captured = if thread::panicking() {
+            Some(self.read())
+        } else {
+            let _ = io::stdout().flush();
+            None
+        };
+        for (saved, fd) in self.saved.iter().zip([STDOUT_FILENO, STDERR_FILENO].iter()) {
+            if *saved >= 0 {
+                unsafe {
+                    libc::dup2(*saved, *fd);
+                    libc::close(*saved);
+                }
+            }
+        }
+        if let Some(captured) = captured {
+            let _ = io::stderr().write_all(captured.as_bytes());
+        }
+    }
+}
+
+/// Run the closure with stdout and stderr redirected, returning its result and everything it
+/// wrote - or nothing, if the output couldn't be redirected and was written as usual
+pub(crate) fn capture<F: FnOnce() -> R, R>(f: F) -> (R, String) {
+    let nested = NESTED.with(|nested| nested.replace(true));
+    let _lock = if nested {
+        None
+    } else {
+        // A panic while capturing leaves nothing behind that needs guarding
+        Some(CAPTURING.lock().unwrap_or_else(|err| err.into_inner()))
+    };
+    let _reset = Reset(nested);
+    match Redirect::start() {
+        Ok(mut redirect) => {
+            let result = f();
+            let output = redirect.read();
+            (result, output)
+        }
+        Err(_) => (f(), String::new()),
+    }
+}
+
+/// Restores whether this thread is capturing when dropped
+struct Reset(bool);
+
+impl Drop for Reset {
+    fn drop(&mut self) {
+        let _ = NESTED.try_with(|nested| nested.set(self.0));
+    }
+}
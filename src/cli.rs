@@ -0,0 +1,56 @@
+//! Command line flags for controlling Behold, for use with clap.
+//!
+//! Flatten `BeholdArgs` into an application's own arguments and call `apply` to let users
+//! steer debugging from the command line:
+//!
+//! ```
+//! extern crate behold;
+//! extern crate clap;
+//!
+//! use behold::cli::BeholdArgs;
+//! use clap::Parser;
+//!
+//! #[derive(Parser)]
+//! struct Args {
+//!     #[command(flatten)]
+//!     behold: BeholdArgs,
+//! }
+//!
+//! fn main() {
+//!     let args = Args::parse_from(&["my-program", "--behold", "f3-1", "--behold-tag-filter", "net*"]);
+//!     args.behold.apply().unwrap();
+//! }
+//! ```
+use std::io;
+use std::path::PathBuf;
+
+use behold;
+
+/// Flags for enabling context keys, filtering by tag and redirecting output
+#[derive(clap::Args, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BeholdArgs {
+    /// Enable a behold context key, may be repeated
+    #[arg(long = "behold", value_name = "KEY")]
+    pub keys: Vec<String>,
+    /// Only show behold output whose tag matches one of the comma separated glob patterns;
+    /// patterns with a leading `-` exclude tags
+    #[arg(long = "behold-tag-filter", value_name = "GLOB")]
+    pub tag_filter: Option<String>,
+    /// Append behold output to the specified file instead of printing it
+    #[arg(long = "behold-output", value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+impl BeholdArgs {
+    /// Configure the global Behold context according to the parsed flags
+    pub fn apply(&self) -> io::Result<()> {
+        behold().set_contexts(self.keys.iter().map(|key| (key, true)));
+        if let Some(ref patterns) = self.tag_filter {
+            behold().set_tag_filter(patterns);
+        }
+        if let Some(ref output) = self.output {
+            behold().set_output_file(output)?;
+        }
+        Ok(())
+    }
+}
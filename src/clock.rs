@@ -0,0 +1,113 @@
+//! Sources of the current time, replaceable so that timestamps, rate limits, timers and expiring
+//! context can be tested deterministically.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// A source of the current time, set for all Behold instances with `set_clock`
+pub trait Clock: Send + Sync {
+    /// The current monotonic time, used to measure durations
+    fn now(&self) -> Instant;
+    /// The current wall-clock time, used for timestamps
+    fn system_time(&self) -> SystemTime;
+}
+
+/// The real time, used unless another clock is set
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock which stands still until it's advanced - clones share the same time
+#[derive(Clone, Debug)]
+pub struct TestClock {
+    time: Arc<Mutex<(Instant, SystemTime)>>,
+}
+
+impl TestClock {
+    /// Create a clock stopped at the current time
+    pub fn new() -> Self {
+        TestClock {
+            time: Arc::new(Mutex::new((Instant::now(), SystemTime::now()))),
+        }
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, by: Duration) {
+        if let Ok(mut time) = self.time.lock() {
+            time.0 += by;
+            time.1 += by;
+        }
+    }
+
+    fn read(&self) -> (Instant, SystemTime) {
+        match self.time.lock() {
+            Ok(time) => *time,
+            Err(err) => *err.into_inner(),
+        }
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> Instant {
+        self.read().0
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.read().1
+    }
+}
+
+/// Whether a clock has been set, so that the real time can be read without locking otherwise
+static CUSTOM: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref CLOCK: RwLock<Arc<dyn Clock>> = RwLock::new(Arc::new(SystemClock));
+}
+
+pub(crate) fn set(clock: Arc<dyn Clock>) {
+    match CLOCK.write() {
+        Ok(mut current) => *current = clock,
+        Err(err) => panic!(
+            "set_clock called on an instance of Behold - lock poisoned - {:?}!",
+            err
+        ),
+    }
+    CUSTOM.store(true, Ordering::Release);
+}
+
+/// The current monotonic time according to the clock
+pub(crate) fn now() -> Instant {
+    if !CUSTOM.load(Ordering::Acquire) {
+        return Instant::now();
+    }
+    match CLOCK.read() {
+        Ok(clock) => clock.now(),
+        Err(_) => Instant::now(),
+    }
+}
+
+/// The current wall-clock time according to the clock
+pub(crate) fn system_time() -> SystemTime {
+    if !CUSTOM.load(Ordering::Acquire) {
+        return SystemTime::now();
+    }
+    match CLOCK.read() {
+        Ok(clock) => clock.system_time(),
+        Err(_) => SystemTime::now(),
+    }
+}
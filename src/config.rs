@@ -0,0 +1,180 @@
+//! Loading context keys from configuration files.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex, Once};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use behold;
+use directive::{self, Directive};
+use export;
+
+/// How often a watched file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Parse a configuration file into directives.
+///
+/// Each line holds a directive, such as `key`, `key = true`, `key = off` or `key = 2`, as
+/// parsed by `Behold::parse_directives`. Blank lines, lines starting with `#` and lines which
+/// can't be parsed are ignored.
+pub(crate) fn parse(contents: &str) -> Vec<Directive> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| directive::parse_one(line).ok())
+        .collect()
+}
+
+/// Write (key, value) pairs to the file at the specified path in the format read by `parse`,
+/// replacing its contents
+pub(crate) fn save<'a, I: IntoIterator<Item = (&'a String, &'a bool)>>(
+    path: &Path,
+    pairs: I,
+) -> io::Result<()> {
+    fs::write(path, render(pairs))
+}
+
+/// Render (key, value) pairs in the format read by `parse`, with keys escaped so that they're
+/// read back as they were
+pub(crate) fn render<'a, I: IntoIterator<Item = (&'a String, &'a bool)>>(pairs: I) -> String {
+    let mut contents = String::from("# Context saved by behold\n");
+    for (key, value) in pairs {
+        contents.push_str(&format!("{} = {}\n", export::escape(key), value));
+    }
+    contents
+}
+
+/// Files waiting to be written by the saver thread, with their new contents - only the latest
+/// contents of each are kept, as each save replaces the whole file
+struct Saves {
+    pending: Mutex<BTreeMap<PathBuf, String>>,
+    ready: Condvar,
+}
+
+lazy_static! {
+    static ref SAVES: Saves = Saves {
+        pending: Mutex::new(BTreeMap::new()),
+        ready: Condvar::new(),
+    };
+    /// Held while taking and writing pending saves, so that `flush` and the saver thread
+    /// can't write older contents over newer ones
+    static ref WRITING: Mutex<()> = Mutex::new(());
+}
+
+static SAVER: Once = Once::new();
+
+/// Replace the contents of the file from the saver thread, so that state persisted across runs,
+/// such as the context, is saved without waiting for the disk while holding its lock
+pub(crate) fn save_later(path: &Path, contents: String) {
+    let mut spawned = true;
+    SAVER.call_once(|| {
+        spawned = thread::Builder::new()
+            .name("behold-saver".to_string())
+            .spawn(run_saver)
+            .is_ok();
+    });
+    if let Ok(mut pending) = SAVES.pending.lock() {
+        pending.insert(path.to_path_buf(), contents);
+        SAVES.ready.notify_one();
+    }
+    if !spawned {
+        flush();
+    }
+}
+
+fn run_saver() {
+    loop {
+        if let Ok(mut pending) = SAVES.pending.lock() {
+            while pending.is_empty() {
+                pending = match SAVES.ready.wait(pending) {
+                    Ok(pending) => pending,
+                    Err(_) => return,
+                };
+            }
+        }
+        flush();
+    }
+}
+
+/// Write any pending saves now, waiting for any already being written
+pub(crate) fn flush() {
+    let _writing = WRITING.lock();
+    let pending = SAVES
+        .pending
+        .lock()
+        .map(|mut pending| mem::take(&mut *pending))
+        .unwrap_or_default();
+    for (path, contents) in pending {
+        // There's nowhere to report a failure to save debugging state
+        let _ = fs::write(&path, contents);
+    }
+}
+
+/// Load the file at the specified path into the global context if it exists, then save the
+/// global context back to it whenever it changes
+pub(crate) fn persist(path: &Path) -> io::Result<()> {
+    match fs::read_to_string(path) {
+        Ok(contents) => behold().set_directives(&parse(&contents)),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    behold().context.persist_to(path)
+}
+
+/// Watches a configuration file, applying its keys to the global context whenever it changes
+struct Watcher {
+    path: PathBuf,
+    /// Modification time of the file when it was last loaded
+    modified: Option<SystemTime>,
+    /// Keys set by the previous load, so keys removed from the file can be disabled
+    keys: BTreeSet<String>,
+}
+
+impl Watcher {
+    /// Apply the file's keys to the context, disabling any keys that have since been removed
+    fn load(&mut self) -> io::Result<()> {
+        let modified = fs::metadata(&self.path)?.modified().ok();
+        let contents = fs::read_to_string(&self.path)?;
+        let directives = parse(&contents);
+        let keys: BTreeSet<String> = directives
+            .iter()
+            .map(|directive| directive.key().to_string())
+            .collect();
+        let removed = self.keys.difference(&keys).map(|key| (key.as_str(), false));
+        behold().set_contexts(removed);
+        behold().set_directives(&directives);
+        self.modified = modified;
+        self.keys = keys;
+        Ok(())
+    }
+
+    /// Reload the file whenever its modification time changes
+    fn watch(mut self) {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+            if modified.is_some() && modified != self.modified {
+                // The file may be mid-write; keep the old keys and try again next time
+                let _ = self.load();
+            }
+        }
+    }
+}
+
+/// Load the file at the specified path and keep reloading it from a background thread
+pub(crate) fn watch(path: &Path) -> io::Result<()> {
+    let mut watcher = Watcher {
+        path: path.to_path_buf(),
+        modified: None,
+        keys: BTreeSet::new(),
+    };
+    watcher.load()?;
+    thread::Builder::new()
+        .name("behold-config-watcher".to_string())
+        .spawn(move || watcher.watch())?;
+    Ok(())
+}
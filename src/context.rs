@@ -0,0 +1,273 @@
+//! Layers of context consulted by `when_context`.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LockResult, Mutex, MutexGuard};
+use std::time::Instant;
+
+use {clock, config};
+
+thread_local! {
+    /// Context set with `set_thread_context`, visible only on this thread
+    static THREAD_CONTEXT: RefCell<BTreeMap<String, bool>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// The layer of context a value was resolved from.
+///
+/// Layers are consulted in the order listed here, and the first layer holding the key wins:
+/// instance-local context overrides thread-local context, which overrides global context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ContextLayer {
+    /// Set on the instance itself, with `local_context`
+    Instance,
+    /// Set for the current thread, with `set_thread_context`
+    Thread,
+    /// Set for every instance sharing the context, with `set_context` - this is the global
+    /// context unless the instance was created by `with_context`
+    Global,
+}
+
+/// Context shared by a Behold instance and every instance derived from it
+pub(crate) struct SharedContext {
+    keys: Mutex<BTreeMap<String, bool>>,
+    /// Keys are hashed into 64 buckets, and a bucket's bit is set while any key in it is true,
+    /// so that `when_context` can rule most missing or disabled keys out without locking
+    enabled: AtomicU64,
+    /// Bits set the same way for every key whatever its value, so that a key known not to be
+    /// enabled can be told apart from one that's missing - and so falls back to module patterns
+    present: AtomicU64,
+    /// Whether any key is a module pattern, so that keys missing from the context only need to
+    /// be matched against module patterns when there are some
+    has_module_patterns: AtomicBool,
+    /// Numeric levels set with `set_context_level`, such as verbosity
+    levels: Mutex<BTreeMap<String, u64>>,
+    /// File the keys are saved to whenever they change, if set with `persist_context`
+    persist_path: Mutex<Option<PathBuf>>,
+    /// When keys set with `set_context_ttl` expire - only locked while holding the lock on keys
+    expiries: Mutex<BTreeMap<String, Instant>>,
+    /// Whether any key has an expiry, so that reads needn't check for expired keys otherwise
+    has_expiries: AtomicBool,
+}
+
+/// Suffix of keys which match a module and every module beneath it, such as `myapp::net::*`
+const MODULE_WILDCARD: &str = "::*";
+
+impl SharedContext {
+    pub(crate) fn new(keys: BTreeMap<String, bool>) -> Self {
+        let enabled = AtomicU64::new(summarize(&keys));
+        let present = AtomicU64::new(summarize_present(&keys));
+        let has_module_patterns = AtomicBool::new(has_module_patterns(&keys));
+        SharedContext {
+            keys: Mutex::new(keys),
+            enabled,
+            present,
+            has_module_patterns,
+            levels: Mutex::new(BTreeMap::new()),
+            persist_path: Mutex::new(None),
+            expiries: Mutex::new(BTreeMap::new()),
+            has_expiries: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, BTreeMap<String, bool>>> {
+        self.keys.lock()
+    }
+
+    /// A copy of every numeric level
+    pub(crate) fn levels_snapshot(&self) -> BTreeMap<String, u64> {
+        self.levels
+            .lock()
+            .map(|levels| levels.clone())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn lock_levels(&self) -> LockResult<MutexGuard<'_, BTreeMap<String, u64>>> {
+        self.levels.lock()
+    }
+
+    /// Bring the summary of enabled keys up to date after changing them, while still holding
+    /// the lock, and have them saved if persisting
+    pub(crate) fn publish(&self, keys: &BTreeMap<String, bool>) {
+        self.enabled.store(summarize(keys), Ordering::Release);
+        self.present
+            .store(summarize_present(keys), Ordering::Release);
+        self.has_module_patterns
+            .store(has_module_patterns(keys), Ordering::Release);
+        if let Ok(path) = self.persist_path.lock() {
+            if let Some(ref path) = *path {
+                // Keys which are about to expire shouldn't outlive this run
+                let persisted = keys.iter().filter(|(key, _)| !self.expires(key));
+                config::save_later(path, config::render(persisted));
+            }
+        }
+    }
+
+    /// Save the keys to the file at the specified path now and whenever they change
+    pub(crate) fn persist_to(&self, path: &Path) -> io::Result<()> {
+        match self.persist_path.lock() {
+            Ok(mut persist_path) => *persist_path = Some(path.to_path_buf()),
+            Err(err) => panic!(
+                "persist_context called - mutex already acquired - {:?}!",
+                err
+            ),
+        }
+        match self.keys.lock() {
+            Ok(keys) => config::save(path, keys.iter().filter(|(key, _)| !self.expires(key))),
+            Err(err) => panic!(
+                "persist_context called - mutex already acquired - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set or clear when a key expires, while holding the lock on keys
+    pub(crate) fn set_expiry(&self, key: &str, deadline: Option<Instant>) {
+        if deadline.is_none() && !self.has_expiries.load(Ordering::Acquire) {
+            return;
+        }
+        if let Ok(mut expiries) = self.expiries.lock() {
+            match deadline {
+                Some(deadline) => expiries.insert(key.to_string(), deadline),
+                None => expiries.remove(key),
+            };
+            self.has_expiries
+                .store(!expiries.is_empty(), Ordering::Release);
+        }
+    }
+
+    /// Determine if a key is due to expire
+    pub(crate) fn expires(&self, key: &str) -> bool {
+        self.has_expiries.load(Ordering::Acquire)
+            && self
+                .expiries
+                .lock()
+                .map(|expiries| expiries.contains_key(key))
+                .unwrap_or_default()
+    }
+
+    /// Remove any keys which have expired, while holding the lock on keys
+    pub(crate) fn expire(&self, keys: &mut BTreeMap<String, bool>) {
+        if !self.has_expiries.load(Ordering::Acquire) {
+            return;
+        }
+        let before = keys.len();
+        if let Ok(mut expiries) = self.expiries.lock() {
+            let now = clock::now();
+            expiries.retain(|key, deadline| {
+                let expired = *deadline <= now;
+                if expired {
+                    keys.remove(key);
+                }
+                !expired
+            });
+            self.has_expiries
+                .store(!expiries.is_empty(), Ordering::Release);
+        }
+        if keys.len() != before {
+            self.publish(keys);
+        }
+    }
+
+    /// Determine if any key could match a module path, without locking
+    pub(crate) fn might_match_module(&self) -> bool {
+        self.has_module_patterns.load(Ordering::Acquire)
+    }
+
+    /// Determine if the key could be true, without locking - false means it definitely isn't
+    pub(crate) fn might_be_enabled(&self, key: &str) -> bool {
+        self.enabled.load(Ordering::Acquire) & bucket(key) != 0
+    }
+
+    /// Determine if the key could be set at all, without locking - false means it definitely
+    /// isn't
+    pub(crate) fn might_be_present(&self, key: &str) -> bool {
+        self.present.load(Ordering::Acquire) & bucket(key) != 0
+    }
+}
+
+/// The bit summarizing every enabled key that shares this key's bucket
+fn bucket(key: &str) -> u64 {
+    // FNV-1a, since the standard library's hasher is needlessly slow for short keys
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in key.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    1 << (hash % 64)
+}
+
+fn summarize(keys: &BTreeMap<String, bool>) -> u64 {
+    keys.iter()
+        .filter(|(_, value)| **value)
+        .fold(0, |summary, (key, _)| summary | bucket(key))
+}
+
+fn summarize_present(keys: &BTreeMap<String, bool>) -> u64 {
+    keys.keys().fold(0, |summary, key| summary | bucket(key))
+}
+
+fn has_module_patterns(keys: &BTreeMap<String, bool>) -> bool {
+    keys.keys().any(|key| key.ends_with(MODULE_WILDCARD))
+}
+
+/// Look up the value of the most specific module pattern matching the module path, so that
+/// `myapp::net::*` covers `myapp::net` and `myapp::net::tcp` but not `myapp::network`
+pub(crate) fn match_module(keys: &BTreeMap<String, bool>, module_path: &str) -> Option<bool> {
+    keys.iter()
+        .filter_map(|(key, value)| {
+            let module = key.strip_suffix(MODULE_WILDCARD)?;
+            let rest = module_path.strip_prefix(module)?;
+            if rest.is_empty() || rest.starts_with("::") {
+                Some((module.len(), *value))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|(length, _)| *length)
+        .map(|(_, value)| value)
+}
+
+/// Look up the value of the most specific module pattern matching the module path in
+/// thread-local context
+pub(crate) fn match_thread_module(module_path: &str) -> Option<bool> {
+    THREAD_CONTEXT
+        .try_with(|context| match_module(&context.borrow(), module_path))
+        .unwrap_or_default()
+}
+
+pub(crate) fn set_thread(key: &str, value: bool) {
+    THREAD_CONTEXT.with(|context| {
+        context.borrow_mut().insert(key.to_string(), value);
+    });
+}
+
+pub(crate) fn clear_thread() {
+    let _ = THREAD_CONTEXT.try_with(|context| context.borrow_mut().clear());
+}
+
+pub(crate) fn get_thread(key: &str) -> Option<bool> {
+    THREAD_CONTEXT
+        .try_with(|context| context.borrow().get(key).cloned())
+        .unwrap_or_default()
+}
+
+/// A copy of all thread-local context
+pub(crate) fn thread_snapshot() -> BTreeMap<String, bool> {
+    THREAD_CONTEXT
+        .try_with(|context| context.borrow().clone())
+        .unwrap_or_default()
+}
+
+/// Remove and return all thread-local context
+pub(crate) fn take_thread() -> BTreeMap<String, bool> {
+    THREAD_CONTEXT
+        .try_with(|context| std::mem::take(&mut *context.borrow_mut()))
+        .unwrap_or_default()
+}
+
+/// Replace all thread-local context
+pub(crate) fn restore_thread(restored: BTreeMap<String, bool>) {
+    let _ = THREAD_CONTEXT.try_with(|context| *context.borrow_mut() = restored);
+}
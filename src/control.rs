@@ -0,0 +1,87 @@
+//! Steering a running program from its terminal, by typing commands on stdin.
+use std::io::{self, BufRead};
+use std::thread;
+
+use {timer, Behold};
+
+/// Commands understood by `stdin_control`
+const HELP: &str = "behold commands:
+  set <key> <true|false>  set a context key
+  level <key> <n>         set a context level
+  dump                    show the context, levels and timing report
+  mute                    silence all output
+  unmute                  restore output
+  help                    show this message";
+
+/// Read commands from stdin on a background thread until stdin is closed
+pub(crate) fn start() -> io::Result<()> {
+    thread::Builder::new()
+        .name("behold-stdin-control".to_string())
+        .spawn(|| {
+            let stdin = io::stdin();
+            for line in stdin.lock().lines() {
+                match line {
+                    Ok(line) => run(&line),
+                    Err(_) => return,
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// Run a single command, replying regardless of conditionals
+fn run(line: &str) {
+    let behold = Behold::new();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let reply = match words.as_slice() {
+        [] => return,
+        ["set", key, value] => match value.parse::<bool>() {
+            Ok(value) => {
+                behold.set_context(key, value);
+                format!("{} = {}", key, value)
+            }
+            Err(_) => format!("expected true or false, not {:?}", value),
+        },
+        ["level", key, level] => match level.parse::<u64>() {
+            Ok(level) => {
+                behold.set_context_level(key, level);
+                format!("{} = {}", key, level)
+            }
+            Err(_) => format!("expected a number, not {:?}", level),
+        },
+        ["dump"] => dump(),
+        ["mute"] => {
+            Behold::disable_all();
+            "muted".to_string()
+        }
+        ["unmute"] => {
+            Behold::enable_all();
+            "unmuted".to_string()
+        }
+        ["help"] => HELP.to_string(),
+        _ => format!("unknown command {:?} - try help", line.trim()),
+    };
+    behold.write(&reply);
+}
+
+/// Describe the global context, context levels and counters, and the timing report
+pub(crate) fn dump() -> String {
+    let behold = Behold::new();
+    let context = behold
+        .context_snapshot()
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>();
+    let levels = behold
+        .context
+        .levels_snapshot()
+        .iter()
+        .map(|(key, level)| format!("{}={}", key, level))
+        .collect::<Vec<_>>();
+    format!(
+        "behold dump:\ncontext: {}\nlevels: {}\n{}",
+        context.join(", "),
+        levels.join(", "),
+        timer::report()
+    )
+}
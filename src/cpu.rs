@@ -0,0 +1,64 @@
+//! Measurement of the CPU time used by the current thread.
+use std::time::Duration;
+
+/// CPU time used by the current thread so far, if the platform reports it
+#[cfg(unix)]
+pub(crate) fn thread_time() -> Option<Duration> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut time) } == 0 {
+        Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32))
+    } else {
+        None
+    }
+}
+
+/// CPU time used by the current thread so far, if the platform reports it
+#[cfg(windows)]
+pub(crate) fn thread_time() -> Option<Duration> {
+    use std::os::raw::{c_int, c_void};
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct FileTime {
+        low: u32,
+        high: u32,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> *mut c_void;
+        fn GetThreadTimes(
+            thread: *mut c_void,
+            creation: *mut FileTime,
+            exit: *mut FileTime,
+            kernel: *mut FileTime,
+            user: *mut FileTime,
+        ) -> c_int;
+    }
+
+    let (mut creation, mut exit) = (FileTime::default(), FileTime::default());
+    let (mut kernel, mut user) = (FileTime::default(), FileTime::default());
+    let ok = unsafe {
+        GetThreadTimes(
+            GetCurrentThread(),
+            &mut creation,
+            &mut exit,
+            &mut kernel,
+            &mut user,
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    // File times count 100 nanosecond intervals
+    let ticks = |time: &FileTime| (u64::from(time.high) << 32) | u64::from(time.low);
+    Some(Duration::from_nanos((ticks(&kernel) + ticks(&user)) * 100))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn thread_time() -> Option<Duration> {
+    None
+}
@@ -0,0 +1,89 @@
+//! Suppression of messages identical to one shown within a sliding window of time.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use clock;
+use Level;
+
+/// Messages shown within the window, by content hash
+struct Window {
+    length: Duration,
+    /// When each message was last let through
+    shown: HashMap<u64, Instant>,
+    /// Hashes in the order they were let through, for expiring them
+    order: VecDeque<(Instant, u64)>,
+}
+
+/// Whether duplicates are being suppressed, so that nothing is hashed or locked otherwise
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref WINDOW: Mutex<Option<Window>> = Mutex::new(None);
+}
+
+/// Suppress duplicates of messages shown within the window from now on, or stop if None
+pub(crate) fn set_window(length: Option<Duration>) {
+    match WINDOW.lock() {
+        Ok(mut window) => {
+            *window = length.map(|length| Window {
+                length,
+                shown: HashMap::new(),
+                order: VecDeque::new(),
+            });
+            ACTIVE.store(window.is_some(), Ordering::Relaxed);
+        }
+        Err(err) => panic!(
+            "set_dedup_window called on an instance of Behold - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+}
+
+/// Determine if duplicates are being suppressed
+pub(crate) fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Determine if a message with the same content was let through within the window, letting
+/// this one through and starting its window if not
+pub(crate) fn is_duplicate(
+    msg: &str,
+    tag: Option<&str>,
+    level: Option<Level>,
+    fields: &[(String, String)],
+) -> bool {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return false;
+    }
+    let mut hasher = DefaultHasher::new();
+    (msg, tag, level, fields).hash(&mut hasher);
+    let hash = hasher.finish();
+    let mut window = match WINDOW.lock() {
+        Ok(window) => window,
+        Err(_) => return false,
+    };
+    let window = match *window {
+        Some(ref mut window) => window,
+        None => return false,
+    };
+    let now = clock::now();
+    while let Some(&(shown, expired)) = window.order.front() {
+        if now.duration_since(shown) < window.length {
+            break;
+        }
+        window.order.pop_front();
+        if window.shown.get(&expired) == Some(&shown) {
+            window.shown.remove(&expired);
+        }
+    }
+    if window.shown.contains_key(&hash) {
+        return true;
+    }
+    window.shown.insert(hash, now);
+    window.order.push_back((now, hash));
+    false
+}
@@ -0,0 +1,74 @@
+//! The directive syntax shared by every way of enabling things, such as
+//! `net=on,parser.verbose=2,db=off`.
+use export::{self, ParseError};
+
+/// A single instruction parsed by `Behold::parse_directives`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Directive {
+    /// Set a context key, from `key`, `key=on` or `key=off`
+    Flag(String, bool),
+    /// Set a context level, from `key=2` - the key is enabled too if the level is above 0
+    Level(String, u64),
+}
+
+impl Directive {
+    /// The context key the directive applies to
+    pub fn key(&self) -> &str {
+        match *self {
+            Directive::Flag(ref key, _) | Directive::Level(ref key, _) => key,
+        }
+    }
+
+    /// Whether the directive enables its context key
+    pub fn enabled(&self) -> bool {
+        match *self {
+            Directive::Flag(_, value) => value,
+            Directive::Level(_, level) => level > 0,
+        }
+    }
+}
+
+/// Parse comma separated directives. A bare key, or one set to `on`, `true` or `yes`, is
+/// enabled; a key set to `off`, `false` or `no` is disabled; a key set to a number has that
+/// level. Keys may be escaped with a backslash, as written by `export::escape`.
+pub(crate) fn parse(directives: &str) -> Result<Vec<Directive>, ParseError> {
+    export::split_unescaped(directives, ',')
+        .into_iter()
+        .map(export::trim_escaped)
+        .filter(|entry| !entry.is_empty())
+        .map(parse_one)
+        .collect()
+}
+
+/// Parse a single directive
+pub(crate) fn parse_one(entry: &str) -> Result<Directive, ParseError> {
+    let parts = export::split_unescaped(entry, '=');
+    let key = export::unescape(export::trim_escaped(parts[0]));
+    if key.is_empty() {
+        return Err(ParseError {
+            entry: entry.to_string(),
+            reason: "expected a key",
+        });
+    }
+    let value = match parts.len() {
+        1 => return Ok(Directive::Flag(key, true)),
+        2 => parts[1].trim(),
+        _ => {
+            return Err(ParseError {
+                entry: entry.to_string(),
+                reason: "expected at most one unescaped '='",
+            })
+        }
+    };
+    match value.to_ascii_lowercase().as_str() {
+        "on" | "true" | "yes" => Ok(Directive::Flag(key, true)),
+        "off" | "false" | "no" => Ok(Directive::Flag(key, false)),
+        _ => match value.parse() {
+            Ok(level) => Ok(Directive::Level(key, level)),
+            Err(_) => Err(ParseError {
+                entry: entry.to_string(),
+                reason: "expected on, off, true, false or a level",
+            }),
+        },
+    }
+}
@@ -0,0 +1,42 @@
+//! Strongly-typed diagnostic events, as an alternative to formatting messages by hand.
+
+/// A diagnostic event with a name and key-value fields, shown with `show_event` so that every
+/// event of a type renders the same way in every format - the name as the message and the
+/// fields alongside any attached with `field`.
+/// # Examples
+/// ```
+/// use behold::{behold, BeholdEvent};
+/// struct CacheMiss {
+///     key: String,
+///     shard: u32,
+/// }
+///
+/// impl BeholdEvent for CacheMiss {
+///     fn name(&self) -> &str {
+///         "cache_miss"
+///     }
+///
+///     fn fields(&self) -> Vec<(String, String)> {
+///         vec![
+///             ("key".to_string(), self.key.clone()),
+///             ("shard".to_string(), self.shard.to_string()),
+///         ]
+///     }
+/// }
+///
+/// behold().show_event(&CacheMiss {
+///     key: "user:7".to_string(),
+///     shard: 3,
+/// });
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "cache_miss key=user:7 shard=3"
+/// ```
+pub trait BeholdEvent {
+    /// Name of the event, shown as the message
+    fn name(&self) -> &str;
+
+    /// Key-value pairs describing this occurrence of the event
+    fn fields(&self) -> Vec<(String, String)>;
+}
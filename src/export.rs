@@ -0,0 +1,120 @@
+//! A compact serialization of context, for passing to child processes.
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+
+/// Environment variable from which context is imported at startup
+pub const CONTEXT_ENV_VAR: &str = "BEHOLD_CONTEXT";
+
+/// Error produced when serialized context can't be parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// The entry which couldn't be parsed
+    pub entry: String,
+    /// Why the entry couldn't be parsed
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid context entry {:?} - {}",
+            self.entry, self.reason
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// Serialize context as comma separated directives, with keys escaped by `escape`: keys with
+/// a level are written as `key=2`, and other keys as `key=true` or `key=false`
+pub(crate) fn export(context: &BTreeMap<String, bool>, levels: &BTreeMap<String, u64>) -> String {
+    let keys = context.keys().chain(levels.keys()).collect::<BTreeSet<_>>();
+    keys.into_iter()
+        .map(|key| match levels.get(key) {
+            Some(level) => format!("{}={}", escape(key), level),
+            None => format!("{}={}", escape(key), context[key]),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Escape a key so that it survives `split_unescaped`, `trim_escaped` and being saved to a line
+/// of a file: commas, equals signs, backslashes and `#` are escaped with a backslash, as are
+/// spaces at either end, and line breaks and tabs become `\n`, `\r` and `\t`
+pub(crate) fn escape(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    let last = key.chars().count().saturating_sub(1);
+    for (idx, c) in key.chars().enumerate() {
+        match c {
+            ',' | '=' | '\\' | '#' => escaped.push('\\'),
+            ' ' if idx == 0 || idx == last => escaped.push('\\'),
+            '\n' => {
+                escaped.push_str("\\n");
+                continue;
+            }
+            '\r' => {
+                escaped.push_str("\\r");
+                continue;
+            }
+            '\t' => {
+                escaped.push_str("\\t");
+                continue;
+            }
+            _ => {}
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Undo `escape`
+pub(crate) fn unescape(key: &str) -> String {
+    let mut unescaped = String::with_capacity(key.len());
+    let mut chars = key.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('r') => unescaped.push('\r'),
+                Some('t') => unescaped.push('\t'),
+                Some(escaped) => unescaped.push(escaped),
+                None => {}
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Trim whitespace from both ends, except a space escaped with a backslash
+pub(crate) fn trim_escaped(text: &str) -> &str {
+    let text = text.trim_start();
+    let trimmed = text.trim_end();
+    let backslashes = trimmed.chars().rev().take_while(|c| *c == '\\').count();
+    match text[trimmed.len()..].chars().next() {
+        Some(escaped) if backslashes % 2 == 1 => &text[..trimmed.len() + escaped.len_utf8()],
+        _ => trimmed,
+    }
+}
+
+/// Split on a delimiter, ignoring delimiters escaped with a backslash
+pub(crate) fn split_unescaped(text: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (idx, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            parts.push(&text[start..idx]);
+            start = idx + c.len_utf8();
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
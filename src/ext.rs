@@ -0,0 +1,83 @@
+//! Extension traits which let any value be beheld with a postfix call.
+use std::fmt::{Debug, Display};
+
+use behold;
+
+/// Show values in the middle of an expression, without restructuring the code around them
+pub trait BeholdTap: Sized {
+    /// Show the label and the `Debug` representation of the value if a default behold instance
+    /// would speak up, then return the value unchanged
+    /// # Examples
+    /// ```
+    /// use behold::BeholdTap;
+    /// let total: u32 = vec![1, 2, 3]
+    ///     .into_iter()
+    ///     .map(|x| x * 2)
+    ///     .collect::<Vec<_>>()
+    ///     .behold_tap("doubled")
+    ///     .into_iter()
+    ///     .sum();
+    /// assert_eq!(total, 12);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "doubled: [2, 4, 6]"
+    /// ```
+    fn behold_tap(self, label: &str) -> Self;
+}
+
+impl<T: Debug> BeholdTap for T {
+    fn behold_tap(self, label: &str) -> Self {
+        let behold = behold();
+        if behold.speaking() {
+            behold.emit(&format!("{}: {:?}", label, self));
+        }
+        self
+    }
+}
+
+/// Show values with a single postfix call, in place of `behold().show(format!(...))`
+pub trait BeholdExt {
+    /// Show the value if a default behold instance would speak up
+    /// # Examples
+    /// ```
+    /// use behold::BeholdExt;
+    /// let answer = 42;
+    /// answer.behold();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "42"
+    /// ```
+    fn behold(&self) -> &Self;
+
+    /// Show the value if a behold instance with the given tag would speak up
+    /// # Examples
+    /// ```
+    /// use behold::BeholdExt;
+    /// "Parsing header".behold_tagged("parser");
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Parsing header, parser"
+    /// ```
+    fn behold_tagged(&self, tag: &str) -> &Self;
+}
+
+impl<T: Display + ?Sized> BeholdExt for T {
+    fn behold(&self) -> &Self {
+        let behold = behold();
+        if behold.speaking() {
+            behold.emit(&self.to_string());
+        }
+        self
+    }
+
+    fn behold_tagged(&self, tag: &str) -> &Self {
+        let behold = behold().tag(tag);
+        if behold.speaking() {
+            behold.emit(&self.to_string());
+        }
+        self
+    }
+}
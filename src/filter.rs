@@ -0,0 +1,69 @@
+//! Matching of tags against user supplied filters.
+
+/// A set of glob patterns, any of which may match a tag, along with patterns excluding tags
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TagFilter {
+    patterns: Vec<String>,
+    /// Patterns given with a leading `-`, which exclude tags even if they match `patterns`
+    excluded: Vec<String>,
+}
+
+impl TagFilter {
+    /// Parse a comma separated list of glob patterns, such as `net*,db,-net-noisy`
+    pub(crate) fn parse(patterns: &str) -> Self {
+        let (excluded, patterns): (Vec<&str>, Vec<&str>) = patterns
+            .split(',')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .partition(|pattern| pattern.starts_with('-'));
+        TagFilter {
+            patterns: patterns.into_iter().map(str::to_string).collect(),
+            excluded: excluded
+                .into_iter()
+                .map(|pattern| pattern[1..].trim().to_string())
+                .collect(),
+        }
+    }
+
+    /// Determine if output with the tag, if any, is let through. Untagged output is only let
+    /// through when the filter consists solely of exclusions.
+    pub(crate) fn allows(&self, tag: Option<&str>) -> bool {
+        match tag {
+            Some(tag) => {
+                (self.patterns.is_empty() || self.patterns.iter().any(|pattern| glob(pattern, tag)))
+                    && !self.excluded.iter().any(|pattern| glob(pattern, tag))
+            }
+            None => self.patterns.is_empty(),
+        }
+    }
+}
+
+/// Match text against a glob pattern where `*` matches any run of characters and `?` matches
+/// exactly one
+pub(crate) fn glob(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match(&pattern, &text)
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` seen and the text position it was tried against
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, star_t)) = backtrack {
+            p = star + 1;
+            t = star_t + 1;
+            backtrack = Some((star, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
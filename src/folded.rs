@@ -0,0 +1,108 @@
+//! Recording of nested scopes and timers as folded stacks, for rendering with inferno or
+//! flamegraph.pl.
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use clock;
+
+/// A scope or timer which is still running
+struct Frame {
+    label: String,
+    started: Instant,
+    /// Time spent in frames nested within this one
+    children: Duration,
+}
+
+lazy_static! {
+    /// File that folded stacks are appended to, if any
+    static ref FILE: Mutex<Option<File>> = Mutex::new(None);
+}
+
+/// Whether folded stacks are being recorded, so that scopes needn't take the lock otherwise
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Frames entered on this thread while recording, outermost first
+    static FRAMES: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Stop recording folded stacks and close the file
+pub(crate) fn close() {
+    RECORDING.store(false, Ordering::Relaxed);
+    if let Ok(mut folded) = FILE.lock() {
+        if let Some(file) = folded.take() {
+            let _ = file.sync_all();
+        }
+    }
+}
+
+/// Append folded stacks to the file at the specified path from now on
+pub(crate) fn set_file(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    match FILE.lock() {
+        Ok(mut folded) => *folded = Some(file),
+        Err(err) => panic!(
+            "set_folded_stack_file called - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+    RECORDING.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Enter a frame if recording, returning its position on this thread's stack
+pub(crate) fn enter(label: &str) -> Option<usize> {
+    if !RECORDING.load(Ordering::Relaxed) {
+        return None;
+    }
+    FRAMES
+        .try_with(|frames| {
+            let mut frames = frames.borrow_mut();
+            frames.push(Frame {
+                // `;` separates frames in the folded format
+                label: label.replace(';', ":"),
+                started: clock::now(),
+                children: Duration::default(),
+            });
+            frames.len() - 1
+        })
+        .ok()
+}
+
+/// Exit the frame at the position returned by `enter`, writing a line giving the stack and
+/// the microseconds spent in the frame itself rather than in frames nested within it
+pub(crate) fn exit(position: usize) {
+    let line = FRAMES.try_with(|frames| {
+        let mut frames = frames.borrow_mut();
+        if frames.len() <= position {
+            return None;
+        }
+        // Frames left behind by guards dropped out of order end along with this one
+        frames.truncate(position + 1);
+        let stack = frames
+            .iter()
+            .map(|frame| frame.label.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let frame = frames.pop()?;
+        let elapsed = clock::now().saturating_duration_since(frame.started);
+        if let Some(parent) = frames.last_mut() {
+            parent.children += elapsed;
+        }
+        let own = elapsed.checked_sub(frame.children).unwrap_or_default();
+        Some(format!("{} {}\n", stack, own.as_micros()))
+    });
+    if let Ok(Some(line)) = line {
+        if let Ok(mut file) = FILE.lock() {
+            if let Some(ref mut file) = *file {
+                // A single write keeps lines from several threads from interleaving
+                let _ = file.write_all(line.as_bytes());
+            }
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! Output formats used when a Behold instance speaks up.
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use {Level, Location, Theme};
+
+/// A single message on its way to the output
+pub(crate) struct Record<'a> {
+    /// The message being shown
+    pub msg: &'a str,
+    /// Tag of the Behold instance showing the message
+    pub tag: Option<&'a str>,
+    /// Prefix of the Behold instance showing the message
+    pub prefix: Option<&'a str>,
+    /// Trail of tag scopes active when the message was shown, if enabled
+    pub breadcrumb: Option<&'a str>,
+    /// Key-value pairs attached to the message
+    pub fields: &'a [(String, String)],
+    /// Where the message was shown from, if known
+    pub location: Option<&'a Location>,
+    /// When the message was shown
+    pub time: SystemTime,
+    /// Position of the message in the order all messages were shown in, if enabled
+    pub sequence: Option<u64>,
+    /// Name of the thread the message was shown from, if enabled
+    pub thread: Option<&'a str>,
+    /// How severe the message is, if set
+    pub level: Option<Level>,
+}
+
+/// Determines how messages are rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Render the message followed by the tag, if any: `Hello world!, apples`
+    #[default]
+    Plain,
+    /// Render the message as a logfmt line: `msg="Hello world!" tag=apples ts=2018-03-04T12:00:00.000Z`
+    Logfmt,
+    /// Render the message as a `timestamp,tag,value` CSV row, with the timestamp in seconds since the unix epoch
+    Csv,
+}
+
+/// Determines how messages spanning several lines are rendered
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Multiline {
+    /// Render the message as-is, so continuation lines carry no tag or timestamp
+    #[default]
+    Preserve,
+    /// Render every line as if it were a message of its own, so each carries the tag and timestamp
+    Prefix,
+    /// Render the first line as usual and indent the continuation lines beneath it
+    Indent,
+}
+
+/// Indentation used for continuation lines by `Multiline::Indent`
+const INDENT: &str = "    ";
+
+/// Determines how tags are rendered by `Format::Plain`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagDecoration {
+    /// Template in which `{}` is replaced by the tag
+    template: String,
+    /// Whether the decorated tag leads the line rather than trailing it
+    leading: bool,
+}
+
+impl TagDecoration {
+    /// Append the tag to the end of the line, rendered with the template in which `{}` is
+    /// replaced by the tag, such as `" [{}]"` or `" #{}"`
+    pub fn trailing(template: &str) -> Self {
+        TagDecoration {
+            template: template.to_string(),
+            leading: false,
+        }
+    }
+
+    /// Prepend the tag to the start of the line, rendered with the template in which `{}` is
+    /// replaced by the tag, such as `"[{}] "` or `"({}) "`
+    pub fn leading(template: &str) -> Self {
+        TagDecoration {
+            template: template.to_string(),
+            leading: true,
+        }
+    }
+
+    fn decorate(&self, tag: &str) -> String {
+        self.template.replace("{}", tag)
+    }
+}
+
+impl Default for TagDecoration {
+    /// The original `msg, tag` rendering
+    fn default() -> Self {
+        TagDecoration::trailing(", {}")
+    }
+}
+
+/// Global settings controlling how records are rendered
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Style {
+    pub format: Format,
+    pub multiline: Multiline,
+    /// Whether records carry the trail of active tag scopes
+    pub breadcrumbs: bool,
+    pub tag_decoration: TagDecoration,
+    /// Whether source locations are rendered as OSC-8 terminal hyperlinks to the source file
+    pub hyperlinks: bool,
+    /// Icons prefixing the output of each tag
+    pub tag_icons: BTreeMap<String, String>,
+    /// Whether records carry a sequence number
+    pub sequence_numbers: bool,
+    /// Whether records carry the name of the thread they were shown from
+    pub thread_tags: bool,
+    /// Colors levels are rendered in, if any
+    pub theme: Option<Theme>,
+    /// Whether levels are painted in the theme's colors, as they are only for terminal stdout
+    pub colors: bool,
+}
+
+impl Style {
+    /// Render a record as one or more lines of output
+    pub(crate) fn render(&self, record: &Record) -> String {
+        if !record.msg.contains('\n') {
+            return self.render_line(record);
+        }
+        match self.multiline {
+            Multiline::Preserve => self.render_line(record),
+            Multiline::Prefix => record
+                .msg
+                .lines()
+                .map(|line| {
+                    self.render_line(&Record {
+                        msg: line,
+                        ..*record
+                    })
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Multiline::Indent => {
+                let mut lines = record.msg.lines();
+                let header = Record {
+                    msg: lines.next().unwrap_or_default(),
+                    ..*record
+                };
+                let mut rendered = self.render_line(&header);
+                for line in lines {
+                    rendered.push('\n');
+                    rendered.push_str(INDENT);
+                    rendered.push_str(line);
+                }
+                rendered
+            }
+        }
+    }
+
+    /// Render a record as a single line of output
+    fn render_line(&self, record: &Record) -> String {
+        match self.format {
+            Format::Plain => {
+                let mut line = String::new();
+                if let Some(level) = record.level {
+                    match self.theme {
+                        Some(ref theme) if self.colors => line.push_str(&theme.paint(level)),
+                        _ => line.push_str(&level.to_string()),
+                    }
+                    line.push(' ');
+                }
+                if let Some(breadcrumb) = record.breadcrumb {
+                    line.push_str(&format!("[{}] ", breadcrumb));
+                }
+                if let Some(prefix) = record.prefix {
+                    line.push_str(&format!("{}: ", prefix));
+                }
+                if let Some(location) = record.location {
+                    let text = format!("{}:{}", location.file, location.line);
+                    if self.hyperlinks {
+                        line.push_str(&hyperlink(&location_url(location), &text));
+                    } else {
+                        line.push_str(&text);
+                    }
+                    line.push(' ');
+                }
+                line.push_str(record.msg);
+                for (key, value) in record.fields {
+                    line.push_str(&format!(" {}={}", key, value));
+                }
+                if let Some(tag) = record.tag {
+                    let decorated = self.tag_decoration.decorate(tag);
+                    if self.tag_decoration.leading {
+                        line.insert_str(0, &decorated);
+                    } else {
+                        line.push_str(&decorated);
+                    }
+                    if let Some(icon) = self.tag_icons.get(tag) {
+                        line.insert_str(0, &format!("{} ", icon));
+                    }
+                }
+                if let Some(thread) = record.thread {
+                    let decorated = self.tag_decoration.decorate(thread);
+                    if self.tag_decoration.leading {
+                        line.insert_str(0, &decorated);
+                    } else {
+                        line.push_str(&decorated);
+                    }
+                }
+                if let Some(sequence) = record.sequence {
+                    line.insert_str(0, &format!("#{} ", sequence));
+                }
+                line
+            }
+            Format::Logfmt => {
+                let mut line = String::new();
+                if let Some(sequence) = record.sequence {
+                    line.push_str(&format!("seq={} ", sequence));
+                }
+                if let Some(prefix) = record.prefix {
+                    line.push_str(&format!("prefix={} ", logfmt_value(prefix)));
+                }
+                if let Some(level) = record.level {
+                    line.push_str(&format!("level={} ", level.as_str()));
+                }
+                line.push_str(&format!("msg={}", logfmt_quoted(record.msg)));
+                if let Some(tag) = record.tag {
+                    line.push_str(&format!(" tag={}", logfmt_value(tag)));
+                }
+                if let Some(thread) = record.thread {
+                    line.push_str(&format!(" thread={}", logfmt_value(thread)));
+                }
+                if let Some(breadcrumb) = record.breadcrumb {
+                    line.push_str(&format!(" breadcrumb={}", logfmt_quoted(breadcrumb)));
+                }
+                for (key, value) in record.fields {
+                    line.push_str(&format!(" {}={}", key, logfmt_value(value)));
+                }
+                if let Some(location) = record.location {
+                    line.push_str(&format!(
+                        " file={} line={}",
+                        logfmt_value(location.file),
+                        location.line
+                    ));
+                }
+                line.push_str(&format!(" ts={}", rfc3339(record.time)));
+                line
+            }
+            Format::Csv => format!(
+                "{},{},{}",
+                epoch_seconds(record.time),
+                csv_field(record.tag.unwrap_or_default()),
+                csv_field(record.msg)
+            ),
+        }
+    }
+}
+
+/// Wrap text in an OSC-8 escape sequence so that supporting terminals make it a clickable link
+fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// The path of a source location's file, resolving `file!()` paths against the workspace root
+/// containing the manifest directory captured by `behold!`
+fn source_path(location: &Location) -> PathBuf {
+    let file = Path::new(location.file);
+    if file.is_absolute() {
+        return file.to_path_buf();
+    }
+    match location.manifest_dir {
+        Some(manifest_dir) => workspace_root(Path::new(manifest_dir), file).join(file),
+        // Locations from `log` and `tracing` don't say which crate they're in, so the best
+        // guess is the directory cargo was run from
+        None => env::current_dir()
+            .map(|dir| dir.join(file))
+            .unwrap_or_else(|_| file.to_path_buf()),
+    }
+}
+
+/// The directory `file!()` paths are relative to: the closest ancestor of the manifest
+/// directory whose path down to it begins the file's path, as for a workspace member, or else
+/// the manifest directory itself
+fn workspace_root<'a>(manifest_dir: &'a Path, file: &Path) -> &'a Path {
+    manifest_dir
+        .ancestors()
+        .skip(1)
+        .find(|root| {
+            manifest_dir
+                .strip_prefix(root)
+                .is_ok_and(|member| file.starts_with(member))
+        })
+        .unwrap_or(manifest_dir)
+}
+
+/// A `file://` URL for a source location, with the line number as the fragment as understood
+/// by terminals such as kitty and WezTerm
+fn location_url(location: &Location) -> String {
+    let path = source_path(location);
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut url = String::from("file://");
+    // Windows paths such as `C:/src` need a leading slash to form a valid URL
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b':' | b'-' | b'_' | b'.' | b'~' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    format!("{}#{}", url, location.line)
+}
+
+/// Quote a CSV field only if it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render a time as fractional seconds since the unix epoch
+fn epoch_seconds(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!(
+        "{}.{:03}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Truncate a message to at most `max` bytes, noting how many bytes were cut off
+pub(crate) fn truncate(msg: &str, max: usize) -> Cow<'_, str> {
+    if msg.len() <= max {
+        return Cow::Borrowed(msg);
+    }
+    let mut end = max;
+    while !msg.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!("{}… (+{} bytes)", &msg[..end], msg.len() - end))
+}
+
+/// Render the length of the items and the first and last `k` of them, eliding any in between
+pub(crate) fn slice_summary<T: Debug>(items: &[T], k: usize) -> String {
+    let rendered: Vec<String> = if items.len() <= k.saturating_mul(2) {
+        items.iter().map(|item| format!("{:?}", item)).collect()
+    } else {
+        let first = items[..k].iter().map(|item| format!("{:?}", item));
+        let last = items[items.len() - k..]
+            .iter()
+            .map(|item| format!("{:?}", item));
+        first
+            .chain(Some(format!("… {} more", items.len() - 2 * k)))
+            .chain(last)
+            .collect()
+    };
+    format!("len {}: [{}]", items.len(), rendered.join(", "))
+}
+
+/// Render bytes as text, escaping anything that isn't printable ASCII, such as `\x00` or `\n`
+pub(crate) fn escape_bytes(bytes: &[u8]) -> String {
+    let mut escaped = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        match byte {
+            b'\\' => escaped.push_str("\\\\"),
+            b'\n' => escaped.push_str("\\n"),
+            b'\r' => escaped.push_str("\\r"),
+            b'\t' => escaped.push_str("\\t"),
+            0x20..=0x7e => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    escaped
+}
+
+/// Render a JSON string
+pub(crate) fn json_string(value: &str) -> String {
+    let mut rendered = String::with_capacity(value.len() + 2);
+    rendered.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => rendered.push_str("\\\""),
+            '\\' => rendered.push_str("\\\\"),
+            '\n' => rendered.push_str("\\n"),
+            '\r' => rendered.push_str("\\r"),
+            '\t' => rendered.push_str("\\t"),
+            c if (c as u32) < 0x20 => rendered.push_str(&format!("\\u{:04x}", c as u32)),
+            c => rendered.push(c),
+        }
+    }
+    rendered.push('"');
+    rendered
+}
+
+/// Quote a logfmt value only if it would otherwise be ambiguous
+fn logfmt_value(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+    if needs_quotes {
+        logfmt_quoted(value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Unconditionally quote a logfmt value, escaping as needed
+fn logfmt_quoted(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Render a time as an RFC 3339 UTC timestamp with millisecond precision
+pub(crate) fn rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        (secs_of_day % 3600) / 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Convert days since the unix epoch into a (year, month, day) triple
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
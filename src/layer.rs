@@ -0,0 +1,138 @@
+//! A tracing layer surfacing selected events through behold, for apps already instrumented
+//! with tracing.
+use std::fmt;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use {Behold, Level, Location};
+
+/// Shows tracing events whose target is enabled in context, gated the way `when_context` is:
+/// an event is shown when its target is set to true, or failing that when a module pattern
+/// such as `myapp::net::*` covers the module it came from. The usual conditionals such as tag
+/// filters apply too, with the target as the tag.
+///
+/// The event's `message` is shown at its level, with its other fields attached.
+/// # Examples
+/// ```
+/// # extern crate behold;
+/// # extern crate tracing;
+/// # extern crate tracing_subscriber;
+/// # fn main() {
+/// use behold::{behold, BeholdLayer};
+/// use tracing_subscriber::layer::SubscriberExt;
+/// let subscriber = tracing_subscriber::registry().with(BeholdLayer::new());
+/// tracing::subscriber::with_default(subscriber, || {
+///     behold().set_context("net", true);
+///     tracing::debug!(target: "net", peer = 7, "Connected");
+///     tracing::debug!(target: "db", "Queried");
+/// });
+/// # }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "DEBUG src/main.rs:6 Connected peer=7, net"
+/// ```
+#[derive(Clone, Default)]
+pub struct BeholdLayer {
+    /// Field naming the context key which gates each event, in place of its target
+    key_field: Option<String>,
+}
+
+impl BeholdLayer {
+    /// Create a layer gating events by their target
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gate events carrying the named field by the context key it holds rather than by their
+    /// target, so that `debug!(behold = "cache", "Miss")` is shown when `cache` is enabled
+    /// # Examples
+    /// ```
+    /// # extern crate behold;
+    /// # extern crate tracing;
+    /// # extern crate tracing_subscriber;
+    /// # fn main() {
+    /// use behold::{behold, BeholdLayer};
+    /// use tracing_subscriber::layer::SubscriberExt;
+    /// let subscriber = tracing_subscriber::registry().with(BeholdLayer::new().key_field("behold"));
+    /// tracing::subscriber::with_default(subscriber, || {
+    ///     behold().set_context("cache", true);
+    ///     tracing::debug!(behold = "cache", "Miss");
+    /// });
+    /// # }
+    /// ```
+    pub fn key_field(&self, name: &str) -> Self {
+        BeholdLayer {
+            key_field: Some(name.to_string()),
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for BeholdLayer {
+    fn on_event(&self, event: &Event, _ctx: Context<S>) {
+        let metadata = event.metadata();
+        let mut visitor = Visitor {
+            key_field: self.key_field.as_deref(),
+            key: None,
+            message: String::new(),
+            fields: Vec::new(),
+        };
+        event.record(&mut visitor);
+        let key = visitor.key.as_deref().unwrap_or_else(|| metadata.target());
+        let level = match *metadata.level() {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            tracing::Level::DEBUG => Level::Debug,
+            tracing::Level::TRACE => Level::Trace,
+        };
+        let mut behold = Behold::new().tag(metadata.target()).level(level);
+        if let (Some(file), Some(line), Some(module_path)) =
+            (metadata.file(), metadata.line(), metadata.module_path())
+        {
+            behold = behold.location(Location::new(file, line, module_path));
+        }
+        let behold = behold.when_context(key);
+        if !behold.is_speaking() {
+            return;
+        }
+        visitor
+            .fields
+            .into_iter()
+            .fold(behold, |behold, (name, value)| behold.field(name, value))
+            .show(visitor.message);
+    }
+}
+
+/// Collects the message and fields of an event
+struct Visitor<'a> {
+    key_field: Option<&'a str>,
+    /// Context key held by the key field, if the event has one
+    key: Option<String>,
+    message: String,
+    fields: Vec<(&'static str, String)>,
+}
+
+impl<'a> Visitor<'a> {
+    fn record(&mut self, field: &Field, value: String) {
+        if field.name() == "message" {
+            self.message = value;
+        } else if Some(field.name()) == self.key_field {
+            self.key = Some(value);
+        } else {
+            self.fields.push((field.name(), value));
+        }
+    }
+}
+
+impl<'a> Visit for Visitor<'a> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record(field, format!("{:?}", value));
+    }
+}
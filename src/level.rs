@@ -0,0 +1,140 @@
+//! Severity levels attached to messages, and the colors they're rendered in.
+use std::fmt;
+
+/// How severe a message is, attached with `level`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Something failed
+    Error,
+    /// Something looks wrong, but carried on
+    Warn,
+    /// Something worth knowing happened
+    Info,
+    /// Detail useful while debugging
+    Debug,
+    /// Detail useful only while tracing execution step by step
+    Trace,
+}
+
+impl Level {
+    /// Name of the level in lowercase, as rendered in `Format::Logfmt`
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        match name {
+            "error" => Some(Level::Error),
+            "warn" => Some(Level::Warn),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+/// Renders the level in uppercase, as in `Format::Plain`
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.as_str().to_uppercase())
+    }
+}
+
+/// A terminal color for rendering levels
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    /// Grey in most terminals
+    BrightBlack,
+}
+
+impl Color {
+    /// The ANSI SGR code selecting the color as the foreground
+    fn code(&self) -> u8 {
+        match *self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::BrightBlack => 90,
+        }
+    }
+}
+
+/// The color each level is rendered in by `Format::Plain`, set with `set_theme` - None
+/// leaves a level uncolored. Override the defaults with struct update syntax:
+/// `Theme { info: None, ..Theme::default() }`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Theme {
+    /// Color of `Level::Error`
+    pub error: Option<Color>,
+    /// Color of `Level::Warn`
+    pub warn: Option<Color>,
+    /// Color of `Level::Info`
+    pub info: Option<Color>,
+    /// Color of `Level::Debug`
+    pub debug: Option<Color>,
+    /// Color of `Level::Trace`
+    pub trace: Option<Color>,
+}
+
+impl Default for Theme {
+    /// Red errors, yellow warnings, green info, blue debug and grey trace
+    fn default() -> Self {
+        Theme {
+            error: Some(Color::Red),
+            warn: Some(Color::Yellow),
+            info: Some(Color::Green),
+            debug: Some(Color::Blue),
+            trace: Some(Color::BrightBlack),
+        }
+    }
+}
+
+impl Theme {
+    /// A theme leaving every level uncolored
+    pub fn plain() -> Self {
+        Theme {
+            error: None,
+            warn: None,
+            info: None,
+            debug: None,
+            trace: None,
+        }
+    }
+
+    /// The color the level is rendered in, if any
+    pub fn color(&self, level: Level) -> Option<Color> {
+        match level {
+            Level::Error => self.error,
+            Level::Warn => self.warn,
+            Level::Info => self.info,
+            Level::Debug => self.debug,
+            Level::Trace => self.trace,
+        }
+    }
+
+    /// Render the level's name in its color
+    pub(crate) fn paint(&self, level: Level) -> String {
+        match self.color(level) {
+            Some(color) => format!("\x1b[{}m{}\x1b[0m", color.code(), level),
+            None => level.to_string(),
+        }
+    }
+}
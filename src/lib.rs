@@ -2,38 +2,308 @@
 //! `behold` is a simple library that allows contextual debugging.
 #[macro_use]
 extern crate lazy_static;
+#[cfg(feature = "macros")]
+extern crate behold_macros;
+#[cfg(feature = "clap")]
+extern crate clap;
+#[cfg(all(
+    any(
+        feature = "breakpoint",
+        feature = "capture-output",
+        feature = "cpu-time",
+        feature = "mem",
+        feature = "shared-memory",
+        feature = "signal-dump"
+    ),
+    unix
+))]
+extern crate libc;
+#[cfg(feature = "log")]
+extern crate log;
+#[cfg(feature = "otel")]
+extern crate opentelemetry;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "ron")]
+extern crate ron;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "json")]
+extern crate serde_json;
+#[cfg(feature = "yaml")]
+extern crate serde_yaml;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "tracing")]
+extern crate tracing_subscriber;
 
+mod allocs;
+mod background;
+mod banner;
+#[cfg(feature = "breakpoint")]
+mod breakpoint;
+#[cfg(feature = "async")]
+mod call_async;
+#[cfg(all(feature = "capture-output", unix))]
+mod capture;
+#[cfg(feature = "clap")]
+pub mod cli;
+mod clock;
+mod config;
+mod context;
+mod control;
+#[cfg(feature = "cpu-time")]
+mod cpu;
+mod dedup;
+mod directive;
+mod event;
+mod export;
+mod ext;
+mod filter;
+mod folded;
+mod format;
+#[cfg(feature = "tracing")]
+mod layer;
+mod level;
+mod limit;
+#[cfg(feature = "log")]
+mod logger;
+#[cfg(feature = "mem")]
+mod mem;
+mod middleware;
+mod on_drop;
+mod once;
+#[cfg(feature = "otel")]
+mod otel;
+mod output;
+mod rate;
+mod record;
+mod redact;
+mod scope;
+mod session;
+#[cfg(all(feature = "shared-memory", unix))]
+mod shared;
+#[cfg(all(feature = "signal-dump", unix))]
+mod signal;
+mod sink;
+mod site;
+mod static_tags;
+mod stats;
+#[cfg(feature = "status-endpoint")]
+mod status;
+mod summary;
+pub mod test;
+mod timer;
+mod trace;
+mod watch;
+
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
-use std::sync::{Arc, Mutex};
+use std::fmt::{self, Debug, Display, Write};
+use std::io;
+#[cfg(feature = "status-endpoint")]
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+pub use allocs::{AllocCount, CountingAllocator};
+pub use background::Overflow;
+/// Trace entry to and exit from every method in an `impl` block, tagged with the type name.
+///
+/// Each method starts with `behold().tag(TypeName).trace_call(method_name)`, so the usual
+/// conditionals such as tag filters apply. `const fn`s are left alone.
+/// # Examples
+/// ```
+/// # extern crate behold;
+/// struct Parser;
+///
+/// #[behold::trace_impl]
+/// impl Parser {
+///     fn parse(&self, input: &str) -> usize {
+///         self.count(input)
+///     }
+///
+///     fn count(&self, input: &str) -> usize {
+///         input.len()
+///     }
+/// }
+/// # fn main() {
+/// Parser.parse("Hello world!");
+/// # }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// -> parse, Parser
+/// -> count, Parser
+/// <- count (1.2µs), Parser
+/// <- parse (10.5µs), Parser
+/// ```
+#[cfg(feature = "macros")]
+pub use behold_macros::trace_impl;
+#[cfg(feature = "async")]
+pub use call_async::CallAsync;
+pub use clock::{Clock, SystemClock, TestClock};
+pub use context::ContextLayer;
+use context::SharedContext;
+pub use directive::Directive;
+pub use event::BeholdEvent;
+pub use export::{ParseError, CONTEXT_ENV_VAR};
+pub use ext::{BeholdExt, BeholdTap};
+use filter::TagFilter;
+use format::Style;
+pub use format::{Format, Multiline, TagDecoration};
+#[cfg(feature = "tracing")]
+pub use layer::BeholdLayer;
+pub use level::{Color, Level, Theme};
+use limit::{Admission, RateLimit};
+#[cfg(feature = "log")]
+pub use logger::BeholdLogger;
+pub use middleware::Middleware;
+pub use on_drop::OnDrop;
+pub use record::Record;
+pub use scope::{BufferedScope, CorrelationScope, Scope, TagScope};
+pub use session::{Replay, SessionRecord};
+pub use sink::Sink;
+pub use site::CallSite;
+#[doc(hidden)]
+pub use site::Site;
+pub use static_tags::StaticTags;
+pub use timer::{Timer, TraceCall};
+pub use trace::TraceFile;
 
 /// The core data structure - stores shared global context and instance specific configuration
 #[derive(Clone)]
 pub struct Behold {
     /// Context to determine when to speak up
-    context: Arc<Mutex<BTreeMap<String, bool>>>,
+    context: Arc<SharedContext>,
     /// Determine if this behold instance should produce output
     speak_up: bool,
     /// Suffix used when displaying output
     tag: Option<String>,
+    /// Prefix used when displaying output
+    prefix: Option<String>,
+    /// Key-value pairs attached to output
+    fields: Vec<(String, String)>,
+    /// Where in the source this instance was created, if known
+    location: Option<Location>,
+    /// Context consulted before thread-local and global context
+    local_context: BTreeMap<String, bool>,
+    /// Maximum rate at which this instance may produce output, if limited
+    rate_limit: Option<RateLimit>,
+    /// Maximum length of messages in bytes, overriding the global maximum
+    max_length: Option<usize>,
+    /// Identifies the request or task output belongs to
+    correlation_id: Option<String>,
+    /// How severe output is, if set
+    level: Option<Level>,
+    /// The `behold!` call site this instance was created at, if any
+    site: Option<&'static Site>,
+}
+
+/// A location in the source code, as captured by the `behold!` macro
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// The file containing the call site
+    pub file: &'static str,
+    /// The line of the call site
+    pub line: u32,
+    /// The module containing the call site
+    pub module_path: &'static str,
+    /// The manifest directory of the crate containing the call site, if known, which a
+    /// relative `file` is resolved against for hyperlinks
+    pub manifest_dir: Option<&'static str>,
+}
+
+impl Location {
+    /// Create a new Location, usually via `file!()`, `line!()` and `module_path!()`
+    pub fn new(file: &'static str, line: u32, module_path: &'static str) -> Self {
+        Location {
+            file,
+            line,
+            module_path,
+            manifest_dir: None,
+        }
+    }
+}
+
+/// A point in time which `when_between` can compare against the current time
+pub trait Moment: PartialOrd + Sized {
+    /// The current time
+    fn now() -> Self;
+}
+
+impl Moment for Instant {
+    fn now() -> Self {
+        clock::now()
+    }
+}
+
+impl Moment for SystemTime {
+    fn now() -> Self {
+        clock::system_time()
+    }
+}
+
+impl Default for Behold {
+    fn default() -> Self {
+        let mut behold = BEHOLD.clone();
+        if let Some(context) = test::context() {
+            behold.context = context;
+        }
+        if let Some(tag) = scope::current_tag() {
+            behold.tag = Some(tag);
+        }
+        behold.correlation_id = scope::current_correlation_id();
+        behold
+    }
 }
 
 impl Behold {
     /// Create a new Behold instance
     pub fn new() -> Self {
-        BEHOLD.clone()
+        Self::default()
+    }
+
+    /// Create a new Behold instance with its own context, seeded with the provided keys.
+    ///
+    /// The context is shared by instances derived from this one (via `tag`, `when` and so on)
+    /// but is separate from the global context used by `behold()`.
+    /// # Examples
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use behold::Behold;
+    /// let mut context = BTreeMap::new();
+    /// context.insert("do-it".to_string(), true);
+    /// let seeded = Behold::with_context(context);
+    /// seeded.when_context("do-it").show("Hello world!".to_string());
+    /// seeded.when_context("dont").show("Goodbye world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    pub fn with_context<I: IntoIterator<Item = (String, bool)>>(context: I) -> Self {
+        Behold {
+            context: Arc::new(SharedContext::new(context.into_iter().collect())),
+            ..Self::default()
+        }
     }
 
     /// Set the value of the global Behold context
     /// # Examples
     /// ```
     /// use behold::behold;
-    /// behold().when_context("do-it").show("Hello world!".to_string())
+    /// behold().when_context("do-it").show("Hello world!".to_string());
     /// ```
     /// Will output nothing.
     /// ```
     /// use behold::behold;
     /// behold().set_context("do-it", true);
-    /// behold().when_context("do-it").show("Hello world!".to_string())
+    /// behold().when_context("do-it").show("Hello world!".to_string());
     /// ```
     /// Will produce the output:
     /// ```ignore
@@ -44,6 +314,14 @@ impl Behold {
 
         if let Ok(mut context) = context {
             (*context).insert(key.to_string(), value);
+            self.context.set_expiry(key, None);
+            self.context.publish(&context);
+            #[cfg(all(feature = "shared-memory", unix))]
+            {
+                if self.uses_global_context() {
+                    shared::set(key, value);
+                }
+            }
         } else if let Err(err) = context {
             panic!(
                 "when_context called on an instance of Behold - mutex already acquired - {:?}!",
@@ -52,149 +330,3730 @@ impl Behold {
         }
     }
 
-    /// Return a Behold instance that appends output with the specified tag
+    /// Set the value of a key in the global Behold context only if it currently holds the
+    /// expected value, returning whether it was set. Missing keys hold `false`, as they do for
+    /// `when_context`. The check and the update happen under one lock, so concurrent callers
+    /// can't both flip the same flag.
     /// # Examples
     /// ```
     /// use behold::behold;
-    /// behold().tag("apples").show("Hello world!".to_string());
+    /// assert!(behold().compare_and_set_context("tracing", false, true));
+    /// // Someone else already turned tracing on
+    /// assert!(!behold().compare_and_set_context("tracing", false, true));
+    /// ```
+    pub fn compare_and_set_context(&self, key: &str, expected: bool, new: bool) -> bool {
+        match self.context.lock() {
+            Ok(mut context) => {
+                self.context.expire(&mut context);
+                if context.get(key).cloned().unwrap_or(false) != expected {
+                    return false;
+                }
+                context.insert(key.to_string(), new);
+                self.context.set_expiry(key, None);
+                self.context.publish(&context);
+                #[cfg(all(feature = "shared-memory", unix))]
+                {
+                    if self.uses_global_context() {
+                        shared::set(key, new);
+                    }
+                }
+                true
+            }
+            Err(err) => panic!(
+                "compare_and_set_context called on an instance of Behold - mutex already acquired - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set the value of a key in the global Behold context until the time to live has passed,
+    /// after which the key is removed as if it had never been set, so that temporary debugging
+    /// switches clean up after themselves. Setting the key again with `set_context` keeps it.
+    ///
+    /// Keys with a time to live stay local to this process, even once
+    /// `share_context_across_processes` has been called.
+    /// # Examples
+    /// ```
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use behold::behold;
+    /// behold().set_context_ttl("do-it", true, Duration::from_millis(50));
+    /// behold().when_context("do-it").show("Hello world!".to_string());
+    /// thread::sleep(Duration::from_millis(100));
+    /// behold().when_context("do-it").show("Hello again!".to_string());
+    /// assert_eq!(behold().resolve_context("do-it"), None);
     /// ```
     /// Will produce the output:
     /// ```ignore
-    /// "Hello world!, apples"
+    /// "Hello world!"
     /// ```
-    pub fn tag(&self, tag: &str) -> Self {
-        Behold {
-            context: self.context.clone(),
-            speak_up: self.speak_up,
-            tag: Some(tag.to_string()),
+    pub fn set_context_ttl(&self, key: &str, value: bool, ttl: Duration) {
+        match self.context.lock() {
+            Ok(mut context) => {
+                context.insert(key.to_string(), value);
+                self.context.set_expiry(key, Some(clock::now() + ttl));
+                self.context.publish(&context);
+            }
+            Err(err) => panic!(
+                "set_context_ttl called on an instance of Behold - mutex already acquired - {:?}!",
+                err
+            ),
         }
     }
 
-    /// Produce a behold instance which can speak up or not, depending on the parameter
+    /// Set a numeric level for a context key, such as how verbose a component should be, for
+    /// `when_context_ge` and `when_context_le` to compare against
     /// # Examples
     /// ```
     /// use behold::behold;
-    /// behold().when(0 % 2 == 1).show("Hello world!".to_string())
+    /// behold().set_context_level("parser.verbosity", 2);
+    /// behold()
+    ///     .when_context_ge("parser.verbosity", 1)
+    ///     .show("Parsing".to_string());
+    /// behold()
+    ///     .when_context_ge("parser.verbosity", 3)
+    ///     .show("Parsed token".to_string());
     /// ```
-    /// Will output nothing.
+    /// Will produce the output:
+    /// ```ignore
+    /// "Parsing"
+    /// ```
+    pub fn set_context_level(&self, key: &str, level: u64) {
+        match self.context.lock_levels() {
+            Ok(mut levels) => {
+                levels.insert(key.to_string(), level);
+            }
+            Err(err) => panic!(
+                "set_context_level called on an instance of Behold - mutex already acquired - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Add one to the counter held by a context key, starting from 0 if it isn't set, and return
+    /// the new count. Counters share their values with `set_context_level`.
+    /// # Examples
     /// ```
     /// use behold::behold;
-    /// behold().when(0 % 2 == 0).show("Hello world!".to_string())
+    /// for _ in 0..3 {
+    ///     behold().increment_context_counter("retries");
+    /// }
+    /// assert_eq!(behold().context_level("retries"), Some(3));
     /// ```
-    /// Will output
-    /// ```ignore
-    /// "Hello world!, apples"
+    pub fn increment_context_counter(&self, key: &str) -> u64 {
+        match self.context.lock_levels() {
+            Ok(mut levels) => {
+                let count = levels.entry(key.to_string()).or_insert(0);
+                *count = count.saturating_add(1);
+                *count
+            }
+            Err(err) => panic!(
+                "increment_context_counter called on an instance of Behold - mutex already acquired - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Look up the numeric level set for a context key with `set_context_level`, or None if it
+    /// hasn't been set
+    /// # Examples
     /// ```
-    pub fn when(&self, speak_up: bool) -> Self {
-        Behold {
-            context: self.context.clone(),
-            speak_up: speak_up,
-            tag: self.tag.clone(),
+    /// use behold::behold;
+    /// behold().set_context_level("parser.verbosity", 2);
+    /// assert_eq!(behold().context_level("parser.verbosity"), Some(2));
+    /// assert_eq!(behold().context_level("lexer.verbosity"), None);
+    /// ```
+    pub fn context_level(&self, key: &str) -> Option<u64> {
+        match self.context.lock_levels() {
+            Ok(levels) => levels.get(key).cloned(),
+            Err(err) => panic!(
+                "context_level called on an instance of Behold - mutex already acquired - {:?}!",
+                err
+            ),
         }
     }
 
-    /// Produce a behold instance which can speak up or not, depending on the specified context
+    /// Set the value of a context key for the current thread only, overriding the global
+    /// context on this thread
     /// # Examples
     /// ```
     /// use behold::behold;
-    /// behold().when_context("do-it").show("Hello world!".to_string())
+    /// behold().set_context("do-it", true);
+    /// behold().set_thread_context("do-it", false);
+    /// behold().when_context("do-it").show("Hello world!".to_string());
     /// ```
     /// Will output nothing.
+    pub fn set_thread_context(&self, key: &str, value: bool) {
+        context::set_thread(key, value);
+    }
+
+    /// Remove every key set with `set_thread_context` on the current thread
+    /// # Examples
     /// ```
     /// use behold::behold;
     /// behold().set_context("do-it", true);
-    /// behold().when_context("do-it").show("Hello world!".to_string())
+    /// behold().set_thread_context("do-it", false);
+    /// behold().clear_thread_context();
+    /// behold().when_context("do-it").show("Hello world!".to_string());
     /// ```
-    /// Will output
+    /// Will produce the output:
     /// ```ignore
     /// "Hello world!"
     /// ```
-    pub fn when_context(&self, key: &str) -> Self {
-        let speak_up = match (*self.context).lock() {
-            Ok(context) => (*context).get(&key.to_string()).cloned().unwrap_or_default(),
-            Err(err) => {
-                panic!(
-                    "when_context called on an instance of Behold - mutex already acquired - {:?}!",
-                    err
-                );
-            }
-        };
-        Behold {
-            context: self.context.clone(),
-            speak_up: speak_up,
-            tag: self.tag.clone(),
-        }
+    pub fn clear_thread_context(&self) {
+        context::clear_thread();
     }
 
-    /// Print the provided string if this behold instance is configured to speak up
+    /// Set many keys of the Behold context at once, acquiring the lock only once
     /// # Examples
     /// ```
     /// use behold::behold;
-    /// behold().show("Hello world!".to_string());
+    /// behold().set_contexts(vec![("do-it", true), ("dont", false)]);
+    /// behold().when_context("do-it").show("Hello world!".to_string());
     /// ```
     /// Will produce the output:
     /// ```ignore
     /// "Hello world!"
     /// ```
-    pub fn show(&self, msg: String) {
-        if self.speak_up {
-            if let Some(ref tag) = self.tag {
-                println!("{}, {}", msg, tag);
-            } else {
-                println!("{}", msg);
+    pub fn set_contexts<I, K>(&self, pairs: I)
+    where
+        I: IntoIterator<Item = (K, bool)>,
+        K: AsRef<str>,
+    {
+        match (*self.context).lock() {
+            Ok(mut context) => {
+                for (key, value) in pairs {
+                    let key = key.as_ref();
+                    #[cfg(all(feature = "shared-memory", unix))]
+                    {
+                        if self.uses_global_context() {
+                            shared::set(key, value);
+                        }
+                    }
+                    context.insert(key.to_string(), value);
+                    self.context.set_expiry(key, None);
+                }
+                self.context.publish(&context);
             }
+            Err(err) => panic!(
+                "set_contexts called on an instance of Behold - mutex already acquired - {:?}!",
+                err
+            ),
         }
     }
 
-    /// Call the provided function if this behold instance is configured to speak up
+    /// Serialize the global context and context levels as the directives parsed by
+    /// `parse_directives`, so that they can be passed to a child process via the
+    /// `BEHOLD_CONTEXT` environment variable or a command line argument. A key with a level is
+    /// written as the level, so it's enabled in the child if the level is above 0.
+    ///
+    /// Context is imported from `BEHOLD_CONTEXT` automatically at startup.
+    /// # Examples
+    /// ```no_run
+    /// use std::process::Command;
+    /// use behold::{behold, CONTEXT_ENV_VAR};
+    /// behold().set_context("do-it", true);
+    /// Command::new("my-child-program")
+    ///     .env(CONTEXT_ENV_VAR, behold().export_context())
+    ///     .spawn()
+    ///     .unwrap();
+    /// ```
+    pub fn export_context(&self) -> String {
+        match (*self.context).lock() {
+            Ok(mut context) => {
+                self.context.expire(&mut context);
+                export::export(&context, &self.context.levels_snapshot())
+            }
+            Err(err) => panic!(
+                "export_context called on an instance of Behold - mutex already acquired - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set the global context and context levels from context serialized by `export_context`,
+    /// or any other directives accepted by `apply_directives`
     /// # Examples
     /// ```
     /// use behold::behold;
-    /// behold().call(&|| { println!("Hello world!"); } );
+    /// behold().import_context("do-it=true,dont=false").unwrap();
+    /// behold().when_context("do-it").show("Hello world!".to_string());
     /// ```
-    /// Will output:
+    /// Will produce the output:
     /// ```ignore
     /// "Hello world!"
     /// ```
-    /// Whereas the following:
-    ///```
+    /// Levels survive the round trip:
+    /// ```
     /// use behold::behold;
-    /// behold().when(false).call(&|| { println!("Hello world!"); } );
+    /// behold().set_context("net", true);
+    /// behold().set_context_level("parser.verbosity", 2);
+    /// let exported = behold().export_context();
+    /// assert_eq!(exported, "net=true,parser.verbosity=2");
+    ///
+    /// behold().set_context_level("parser.verbosity", 0);
+    /// behold().import_context(&exported).unwrap();
+    /// assert_eq!(behold().context_level("parser.verbosity"), Some(2));
     /// ```
-    /// Will output nothing.
-    pub fn call(&self, f: &Fn()) {
-        if self.speak_up {
-            f()
+    pub fn import_context(&self, serialized: &str) -> Result<(), ParseError> {
+        self.apply_directives(serialized)
+    }
+
+    /// Parse comma separated directives such as `net=on,parser.verbose=2,db=off` - the syntax
+    /// used by `apply_directives`, the `BEHOLD_CONTEXT` environment variable and the files read
+    /// by `watch_config`.
+    ///
+    /// A bare key, or one set to `on`, `true` or `yes`, is enabled; a key set to `off`, `false`
+    /// or `no` is disabled; a key set to a number has that level, as for `set_context_level`,
+    /// and is enabled if the level is above 0. Commas, equals signs, backslashes, `#` and spaces
+    /// within keys may be escaped with a backslash, and line breaks and tabs written as `\n`,
+    /// `\r` and `\t`.
+    /// # Examples
+    /// ```
+    /// use behold::{Behold, Directive};
+    /// assert_eq!(
+    ///     Behold::parse_directives("net=on,parser.verbose=2,db=off").unwrap(),
+    ///     vec![
+    ///         Directive::Flag("net".to_string(), true),
+    ///         Directive::Level("parser.verbose".to_string(), 2),
+    ///         Directive::Flag("db".to_string(), false),
+    ///     ]
+    /// );
+    /// assert!(Behold::parse_directives("net=maybe").is_err());
+    /// ```
+    pub fn parse_directives(directives: &str) -> Result<Vec<Directive>, ParseError> {
+        directive::parse(directives)
+    }
+
+    /// Apply comma separated directives, as parsed by `parse_directives`, to the global context
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().apply_directives("net=on,parser.verbose=2").unwrap();
+    /// behold().when_context("net").show("Hello world!".to_string());
+    /// behold()
+    ///     .when_context_ge("parser.verbose", 2)
+    ///     .show("Parsing".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!"
+    /// "Parsing"
+    /// ```
+    pub fn apply_directives(&self, directives: &str) -> Result<(), ParseError> {
+        self.set_directives(&directive::parse(directives)?);
+        Ok(())
+    }
+
+    /// Apply parsed directives to the context
+    fn set_directives(&self, directives: &[Directive]) {
+        self.set_contexts(
+            directives
+                .iter()
+                .map(|directive| (directive.key(), directive.enabled())),
+        );
+        for directive in directives {
+            if let Directive::Level(ref key, level) = *directive {
+                self.set_context_level(key, level);
+            }
         }
     }
-}
 
-/// Convenience function for quickly constructing a behold instance.
-///
-/// # Examples
-///
-/// ```rust
-/// use behold::behold;
-/// behold().show("Hello world!".to_string());
-/// ```
-/// Will produce the output:
-/// ```ignore
-/// "Hello world!"
-/// ```
-pub fn behold() -> Behold {
-    Behold::new()
-}
+    /// Share the global context with processes forked after this call, so that `set_context` in
+    /// any one of them is seen by `when_context` in all of them. Keys already set are shared too.
+    ///
+    /// Up to 1024 distinct keys can be shared; keys beyond that remain local to the process
+    /// which set them.
+    /// # Examples
+    /// ```no_run
+    /// use behold::{behold, Behold};
+    /// Behold::share_context_across_processes().unwrap();
+    /// // ... fork worker processes ...
+    /// behold().set_context("do-it", true);
+    /// // Every worker now speaks up
+    /// behold().when_context("do-it").show("Hello world!".to_string());
+    /// ```
+    #[cfg(all(feature = "shared-memory", unix))]
+    pub fn share_context_across_processes() -> io::Result<()> {
+        let already_shared = shared::is_enabled();
+        shared::enable()?;
+        if !already_shared {
+            if let Ok(context) = BEHOLD.context.lock() {
+                for (key, value) in context.iter() {
+                    if !BEHOLD.context.expires(key) {
+                        shared::set(key, *value);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 
-lazy_static! {
-    static ref BEHOLD: Behold = {
-    	Behold {
-	    	context: Arc::new(Mutex::new(BTreeMap::new())),
-	    	speak_up: true,
-	    	tag: None,
-	    }
-	};
-}
+    /// Silence every Behold instance, regardless of `when` or `when_context`
+    ///
+    /// Output is also silenced at startup if the `BEHOLD_SILENT` environment variable is set to
+    /// anything other than an empty string, `0` or `false`.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Behold};
+    /// Behold::disable_all();
+    /// behold().show("Hello world!".to_string());
+    /// Behold::enable_all();
+    /// ```
+    /// Will output nothing.
+    pub fn disable_all() {
+        lazy_static::initialize(&BEHOLD);
+        ENABLED.store(false, Ordering::Relaxed);
+    }
+
+    /// Undo a previous call to `disable_all`, letting Behold instances speak up again
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Behold};
+    /// Behold::disable_all();
+    /// Behold::enable_all();
+    /// behold().show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    pub fn enable_all() {
+        lazy_static::initialize(&BEHOLD);
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+
+    /// Count how many messages each tag and call site shows, has suppressed by `when`,
+    /// `when_context`, tag filters or `disable_all`, and has dropped by rate limits, and print
+    /// the counts when the process exits - so that diagnostics which are silently disabled
+    /// don't stay hidden forever. Call sites are only distinguished for instances created with
+    /// `behold!`.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Behold};
+    /// Behold::summarize_at_exit();
+    /// for i in 0..10 {
+    ///     behold().tag("even").when(i % 2 == 0).show(i.to_string());
+    /// }
+    /// ```
+    /// Will produce the output, after the numbers themselves:
+    /// ```ignore
+    /// behold summary:
+    /// - even: 5 shown, 5 suppressed, 0 throttled
+    /// ```
+    pub fn summarize_at_exit() {
+        summary::enable();
+    }
+
+    /// Show a banner describing the build ahead of the first message shown from now on, with
+    /// the context active at the time, so that captured output says which build produced it.
+    /// This is usually called via the `behold_banner!` macro, which fills in the name, version
+    /// and commit of the crate calling it.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Behold};
+    /// Behold::show_banner("myapp", "1.2.0", Some("3f9c2e1"));
+    /// behold().set_context("net", true);
+    /// behold().show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// myapp 1.2.0 (3f9c2e1) context: net=true
+    /// "Hello world!"
+    /// ```
+    pub fn show_banner(name: &str, version: &str, commit: Option<&str>) {
+        banner::set(name, version, commit);
+    }
+
+    /// Load context keys saved to the file at the specified path by a previous run, if there
+    /// is one, then save the global context back to it whenever it changes - so that debugging
+    /// flags survive restarts during an edit-run cycle. The file uses the same format as
+    /// `watch_config`; keys set with `set_context_ttl` aren't saved. Saves are written from a
+    /// background thread, and finished by `shutdown`.
+    /// # Examples
+    /// ```no_run
+    /// use behold::{behold, Behold};
+    /// Behold::persist_context("behold-context.conf").unwrap();
+    /// // Stays set the next time the program runs
+    /// behold().set_context("do-it", true);
+    /// ```
+    /// Keys are escaped as they're saved, so any key is read back as it was set:
+    /// ```
+    /// use behold::{behold, Behold, Directive};
+    /// let path = std::env::temp_dir().join(format!("behold-doc-{}.conf", std::process::id()));
+    /// Behold::persist_context(&path).unwrap();
+    /// behold().set_context(" net=on, \\db\n#cache ", true);
+    /// // Saves are written in the background, so wait for them
+    /// behold::shutdown();
+    /// let saved = std::fs::read_to_string(&path).unwrap();
+    /// let line = saved.lines().find(|line| line.contains("cache")).unwrap();
+    /// assert_eq!(
+    ///     Behold::parse_directives(line).unwrap(),
+    ///     vec![Directive::Flag(" net=on, \\db\n#cache ".to_string(), true)]
+    /// );
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn persist_context<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        config::persist(path.as_ref())
+    }
+
+    /// Write output from a dedicated writer thread, so that behold calls on latency-critical
+    /// threads never wait for the terminal or the disk. Up to `capacity` messages are queued for
+    /// the writer; what happens to messages shown while the queue is full is decided by
+    /// `set_overflow_policy`, dropping them by default. Logcat, the journal and
+    /// output captured by `test::isolated` are still written immediately.
+    ///
+    /// Call `shutdown` before the process exits, or messages still queued will be lost.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Behold};
+    /// Behold::write_in_background(1024).unwrap();
+    /// behold().show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output, shortly afterwards:
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    pub fn write_in_background(capacity: usize) -> io::Result<()> {
+        background::start(capacity)
+    }
+
+    /// Set what happens to messages shown while the queue of `write_in_background` is full
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Behold, Overflow};
+    /// Behold::write_in_background(16).unwrap();
+    /// behold().set_overflow_policy(Overflow::Block);
+    /// for i in 0..100 {
+    ///     behold().show(format!("Iteration {}", i));
+    /// }
+    /// assert_eq!(behold().dropped_in_background(), 0);
+    /// ```
+    pub fn set_overflow_policy(&self, overflow: Overflow) {
+        background::set_overflow(overflow);
+    }
+
+    /// How many messages have been dropped so far because the queue of `write_in_background`
+    /// was full
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Behold, Overflow};
+    /// Behold::write_in_background(1).unwrap();
+    /// behold().set_overflow_policy(Overflow::DropOldest);
+    /// for i in 0..100 {
+    ///     behold().show(format!("Iteration {}", i));
+    /// }
+    /// behold().show(format!("Dropped {} messages", behold().dropped_in_background()));
+    /// ```
+    pub fn dropped_in_background(&self) -> u64 {
+        background::dropped()
+    }
+
+    /// Dump the global context, context levels and counters, and the timing report whenever
+    /// the process receives the signal - so that a live process can be inspected with
+    /// `kill -QUIT` without restarting it. The dump is written regardless of conditionals, from
+    /// a dedicated thread.
+    /// # Examples
+    /// ```no_run
+    /// # extern crate behold;
+    /// # extern crate libc;
+    /// use behold::Behold;
+    /// # fn main() {
+    /// Behold::dump_on_signal(libc::SIGQUIT).unwrap();
+    /// # }
+    /// ```
+    /// Will produce the output, after `kill -QUIT <pid>`:
+    /// ```ignore
+    /// behold dump:
+    /// context: do-it=true, dont=false
+    /// levels: retries=3
+    /// timing report: no timers have finished
+    /// ```
+    #[cfg(all(feature = "signal-dump", unix))]
+    pub fn dump_on_signal(signal: std::os::raw::c_int) -> io::Result<()> {
+        signal::dump_on(signal)
+    }
+
+    /// Serve a read-only `GET /status` endpoint at the specified address from a background
+    /// thread, returning the address it's listening on. It responds with JSON describing the
+    /// global context, context levels and counters, how many messages each tag has shown,
+    /// suppressed and had throttled since this call, and how output is configured - so that
+    /// dashboards can surface behold state.
+    ///
+    /// The endpoint has no authentication; bind it to a loopback address. Clients have a second
+    /// to send their request and receive the response.
+    /// # Examples
+    /// ```no_run
+    /// use behold::Behold;
+    /// let address = Behold::serve_status("127.0.0.1:0").unwrap();
+    /// println!("curl http://{}/status", address);
+    /// ```
+    /// Will respond with:
+    /// ```ignore
+    /// {"enabled":true,"context":{"do-it":true},"levels":{"retries":3},
+    ///  "tags":{"net":{"shown":12,"suppressed":3,"throttled":0}},
+    ///  "output":{"format":"Plain","file":false,"background":false,"overflow":"DropNewest",
+    ///  "dropped_in_background":0}}
+    /// ```
+    #[cfg(feature = "status-endpoint")]
+    pub fn serve_status<A: ToSocketAddrs>(address: A) -> io::Result<SocketAddr> {
+        status::serve(address)
+    }
+
+    /// Load how many times each call site has spoken up through `once` and `first_n` from the
+    /// file at the specified path, if a previous run saved them there, then save them back
+    /// whenever they change - so that restarting during a long debugging session doesn't show
+    /// the same startup diagnostics again. Counts are saved from a background thread, and
+    /// finished by `shutdown`.
+    /// # Examples
+    /// ```no_run
+    /// #[macro_use]
+    /// extern crate behold;
+    /// use behold::Behold;
+    ///
+    /// fn main() {
+    ///     Behold::persist_once_state("behold-once.txt").unwrap();
+    ///     // Shown on the first run only
+    ///     behold!().once().show("Loaded 12 plugins".to_string());
+    /// }
+    /// ```
+    pub fn persist_once_state<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        once::persist(path.as_ref())
+    }
+
+    /// Load context keys from the file at the specified path, then keep reloading them from a
+    /// background thread whenever the file changes.
+    ///
+    /// Each line of the file holds a directive, as parsed by `parse_directives`, such as a bare
+    /// key, `key = false` or `key = 2`. Blank lines and lines starting with `#` are ignored. Keys
+    /// removed from the file are disabled on the next reload.
+    /// # Examples
+    /// ```no_run
+    /// use behold::{behold, Behold};
+    /// Behold::watch_config("behold.conf").unwrap();
+    /// behold().when_context("do-it").show("Hello world!".to_string());
+    /// ```
+    /// Will output `Hello world!` once `do-it` is added to `behold.conf`.
+    pub fn watch_config<P: AsRef<Path>>(path: P) -> io::Result<()> {
+        config::watch(path.as_ref())
+    }
+
+    /// Print the events per second counted by `tick` for each label from a background thread
+    /// at the interval, starting a new window each time, so that throughput can be watched
+    /// during manual testing. Windows in which nothing was counted are skipped.
+    /// # Examples
+    /// ```no_run
+    /// use behold::{behold, Behold};
+    /// use std::time::Duration;
+    /// Behold::report_rates_every(Duration::from_secs(5)).unwrap();
+    /// loop {
+    ///     behold().tick("requests");
+    ///     // ... handle a request ...
+    /// }
+    /// ```
+    /// Will produce output like this every five seconds:
+    /// ```ignore
+    /// rates over 5.000113s:
+    ///   requests: 6012 (1202.4/s)
+    /// ```
+    pub fn report_rates_every(interval: Duration) -> io::Result<()> {
+        rate::report_every(interval)
+    }
 
+    /// Set the format used by all Behold instances when they speak up
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Format};
+    /// behold().set_format(Format::Logfmt);
+    /// behold().tag("net").show("Hello world!".to_string());
+    /// behold().set_format(Format::Plain);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// msg="Hello world!" tag=net ts=2018-03-04T12:00:00.000Z
+    /// ```
+    pub fn set_format(&self, format: Format) {
+        match STYLE.write() {
+            Ok(mut style) => style.format = format,
+            Err(err) => panic!(
+                "set_format called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set whether all Behold instances include the breadcrumb trail of tag scopes active on
+    /// the current thread, as pushed by `push_tag_scope`, with each message
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_breadcrumbs(true);
+    /// let _app = behold().push_tag_scope("app");
+    /// let _request = behold().push_tag_scope("request");
+    /// let _db = behold().push_tag_scope("db");
+    /// behold().show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "[app > request > db] Hello world!, db"
+    /// ```
+    pub fn set_breadcrumbs(&self, breadcrumbs: bool) {
+        match STYLE.write() {
+            Ok(mut style) => style.breadcrumbs = breadcrumbs,
+            Err(err) => panic!(
+                "set_breadcrumbs called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set whether all Behold instances number their messages from a single global sequence,
+    /// so that output interleaved from several threads can be totally ordered after the fact.
+    /// Numbers lead `Format::Plain` and `Format::Logfmt` lines; `Format::Csv` rows are unchanged.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_sequence_numbers(true);
+    /// behold().show("Hello world!".to_string());
+    /// behold().show("Goodbye world!".to_string());
+    /// behold().set_sequence_numbers(false);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// #0 Hello world!
+    /// #1 Goodbye world!
+    /// ```
+    pub fn set_sequence_numbers(&self, sequence_numbers: bool) {
+        match STYLE.write() {
+            Ok(mut style) => style.sequence_numbers = sequence_numbers,
+            Err(err) => panic!(
+                "set_sequence_numbers called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set whether all Behold instances tag their messages with the name of the thread showing
+    /// them, alongside any tag of their own, so that output needn't be tagged by hand to tell
+    /// threads apart. Unnamed threads are shown by id. The thread is rendered as a tag in
+    /// `Format::Plain` and as a `thread` field in `Format::Logfmt`; `Format::Csv` rows are
+    /// unchanged.
+    /// # Examples
+    /// ```
+    /// use std::thread;
+    /// use behold::behold;
+    /// behold().set_thread_tags(true);
+    /// behold().tag("net").show("Hello world!".to_string());
+    /// thread::Builder::new()
+    ///     .name("worker-1".to_string())
+    ///     .spawn(|| {
+    ///         behold().show("Goodbye world!".to_string());
+    ///     })
+    ///     .unwrap()
+    ///     .join()
+    ///     .unwrap();
+    /// behold().set_thread_tags(false);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!, net, main"
+    /// "Goodbye world!, worker-1"
+    /// ```
+    pub fn set_thread_tags(&self, thread_tags: bool) {
+        match STYLE.write() {
+            Ok(mut style) => style.thread_tags = thread_tags,
+            Err(err) => panic!(
+                "set_thread_tags called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set how all Behold instances render tags in `Format::Plain`, in place of the default
+    /// `msg, tag`
+    /// # Examples
+    /// ```
+    /// use behold::{behold, TagDecoration};
+    /// behold().set_tag_decoration(TagDecoration::leading("[{}] "));
+    /// behold().tag("net").show("Hello world!".to_string());
+    /// behold().set_tag_decoration(TagDecoration::trailing(" #{}"));
+    /// behold().tag("net").show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// [net] Hello world!
+    /// Hello world! #net
+    /// ```
+    pub fn set_tag_decoration(&self, decoration: TagDecoration) {
+        match STYLE.write() {
+            Ok(mut style) => style.tag_decoration = decoration,
+            Err(err) => panic!(
+                "set_tag_decoration called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set the colors levels are rendered in by all Behold instances in `Format::Plain`, or
+    /// None to render them uncolored - the default. Colors are only written to stdout when it's
+    /// a terminal, so files, captured test output, logcat and the journal stay plain text.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Color, Level, Theme};
+    /// behold().set_theme(Some(Theme::default()));
+    /// behold().level(Level::Error).show("Connection lost".to_string());
+    /// behold().set_theme(Some(Theme {
+    ///     warn: Some(Color::Magenta),
+    ///     ..Theme::default()
+    /// }));
+    /// behold().level(Level::Warn).show("Retrying".to_string());
+    /// behold().set_theme(None);
+    /// ```
+    /// Will produce the output, with `ERROR` in red and `WARN` in magenta:
+    /// ```ignore
+    /// ERROR Connection lost
+    /// WARN Retrying
+    /// ```
+    /// Captured output carries no escape codes:
+    /// ```
+    /// use behold::{behold, Level, Theme};
+    /// let output = behold::test::isolated(|| {
+    ///     behold().set_theme(Some(Theme::default()));
+    ///     behold().level(Level::Error).show("Connection lost".to_string());
+    ///     behold().set_theme(None);
+    ///     behold::test::output()
+    /// });
+    /// assert_eq!(output, vec!["ERROR Connection lost"]);
+    /// ```
+    pub fn set_theme(&self, theme: Option<Theme>) {
+        match STYLE.write() {
+            Ok(mut style) => style.theme = theme,
+            Err(err) => panic!(
+                "set_theme called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Prefix the output of every Behold instance with the specified tag with an icon, so that
+    /// different subsystems stand out at a glance in `Format::Plain` output
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_tag_icon("net", "🌐");
+    /// behold().set_tag_icon("db", "🗄");
+    /// behold().tag("net").show("Hello world!".to_string());
+    /// behold().tag("db").show("Goodbye world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// 🌐 Hello world!, net
+    /// 🗄 Goodbye world!, db
+    /// ```
+    pub fn set_tag_icon(&self, tag: &str, icon: &str) {
+        match STYLE.write() {
+            Ok(mut style) => {
+                style.tag_icons.insert(tag.to_string(), icon.to_string());
+            }
+            Err(err) => panic!(
+                "set_tag_icon called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Set how all Behold instances render messages spanning several lines, so that multi-line
+    /// dumps remain attributable
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Multiline};
+    /// behold().set_multiline(Multiline::Prefix);
+    /// behold().tag("dump").show("Hello\nworld!".to_string());
+    /// behold().set_multiline(Multiline::Indent);
+    /// behold().tag("dump").show("Hello\nworld!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// Hello, dump
+    /// world!, dump
+    /// Hello, dump
+    ///     world!
+    /// ```
+    pub fn set_multiline(&self, multiline: Multiline) {
+        match STYLE.write() {
+            Ok(mut style) => style.multiline = multiline,
+            Err(err) => panic!(
+                "set_multiline called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Only produce output from Behold instances whose tag matches one of the comma separated
+    /// glob patterns, in which `*` matches any run of characters and `?` matches exactly one,
+    /// regardless of `when` or `when_context` - much as `RUST_LOG` filters by target.
+    ///
+    /// Patterns with a leading `-` exclude matching tags instead. Untagged instances are
+    /// silenced while a filter is set, unless it consists solely of exclusions.
+    ///
+    /// A filter is also set at startup from the `BEHOLD_TAGS` environment variable, if present.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_tag_filter("net*,db,-net-noisy");
+    /// behold().tag("network").show("Hello world!".to_string());
+    /// behold().tag("net-noisy").show("Chatter".to_string());
+    /// behold().tag("parser").show("Goodbye world!".to_string());
+    /// behold().clear_tag_filter();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!, network"
+    /// ```
+    pub fn set_tag_filter(&self, patterns: &str) {
+        match TAG_FILTER.write() {
+            Ok(mut filter) => {
+                *filter = Some(TagFilter::parse(patterns));
+                TAG_FILTER_SET.store(true, Ordering::Relaxed);
+            }
+            Err(err) => panic!(
+                "set_tag_filter called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Remove the filter set by `set_tag_filter`, letting output from every tag through again
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_tag_filter("net*");
+    /// behold().clear_tag_filter();
+    /// behold().tag("parser").show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!, parser"
+    /// ```
+    pub fn clear_tag_filter(&self) {
+        match TAG_FILTER.write() {
+            Ok(mut filter) => {
+                *filter = None;
+                TAG_FILTER_SET.store(false, Ordering::Relaxed);
+            }
+            Err(err) => panic!(
+                "clear_tag_filter called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Suppress messages from all Behold instances identical to one shown within the window -
+    /// with the same text, tag, level and fields, wherever they were shown from - or stop
+    /// suppressing them if `window` is `None`. A message repeated continually is shown again
+    /// once per window.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// use std::time::Duration;
+    /// behold().set_dedup_window(Some(Duration::from_secs(1)));
+    /// for _ in 0..3 {
+    ///     behold().show("Cache miss".to_string());
+    ///     behold().show("Retrying".to_string());
+    /// }
+    /// behold().set_dedup_window(None);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Cache miss"
+    /// "Retrying"
+    /// ```
+    pub fn set_dedup_window(&self, window: Option<Duration>) {
+        dedup::set_window(window);
+    }
+
+    /// Truncate messages longer than `max` bytes from all Behold instances, or stop truncating
+    /// them if `max` is `None`
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_max_length(Some(5));
+    /// behold().show("Hello world!".to_string());
+    /// behold().set_max_length(None);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello… (+7 bytes)"
+    /// ```
+    pub fn set_max_length(&self, max: Option<usize>) {
+        MAX_LENGTH.store(max.unwrap_or(usize::MAX), Ordering::Relaxed);
+    }
+
+    /// Mask the values of the named field in the output of all Behold instances - both fields
+    /// attached with `field` and assignments within messages such as `password=hunter2`,
+    /// `password: hunter2` or `"password": "hunter2"`. Names are matched case-insensitively.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().redact_field("password");
+    /// behold().show("Logging in with password=hunter2".to_string());
+    /// behold().field("password", "hunter2").show("Logging in".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Logging in with password=***"
+    /// "Logging in password=***"
+    /// ```
+    /// Text in any script is masked, as field names are matched ignoring ASCII case only:
+    /// ```
+    /// use behold::{behold, test};
+    /// behold().redact_field("password");
+    /// test::isolated(|| {
+    ///     behold().show("İstanbul login PASSWORD=hunter2".to_string());
+    ///     assert_eq!(test::output(), vec!["İstanbul login PASSWORD=***".to_string()]);
+    /// });
+    /// ```
+    pub fn redact_field(&self, name: &str) {
+        match redact::REDACTIONS.write() {
+            Ok(mut redactions) => redactions.add_field(name),
+            Err(err) => panic!(
+                "redact_field called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+    }
+
+    /// Mask every match of the regular expression in the output of all Behold instances
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().redact_pattern(r"\d{4}-\d{4}-\d{4}-\d{4}").unwrap();
+    /// behold().show("Charging card 1234-5678-9012-3456".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Charging card ***"
+    /// ```
+    /// Messages are masked before they're truncated by `set_max_length`, so a truncated match
+    /// is masked too:
+    /// ```
+    /// use behold::{behold, test};
+    /// behold().redact_pattern(r"\d{4}-\d{4}-\d{4}-\d{4}").unwrap();
+    /// test::isolated(|| {
+    ///     behold().max_length(22).show("card 1234-5678-9012-3456 ok".to_string());
+    ///     assert_eq!(test::output(), vec!["card *** ok".to_string()]);
+    /// });
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn redact_pattern(&self, pattern: &str) -> Result<(), regex::Error> {
+        let pattern = regex::Regex::new(pattern)?;
+        match redact::REDACTIONS.write() {
+            Ok(mut redactions) => redactions.add_pattern(pattern),
+            Err(err) => panic!(
+                "redact_pattern called on an instance of Behold - lock poisoned - {:?}!",
+                err
+            ),
+        }
+        Ok(())
+    }
+
+    /// Redirect the output of all Behold instances to the specified file, appending to it if it
+    /// already exists
+    /// # Examples
+    /// ```no_run
+    /// use behold::behold;
+    /// behold().set_output_file("behold.log").unwrap();
+    /// behold().show("Hello world!".to_string());
+    /// ```
+    /// Will write `Hello world!` to `behold.log`.
+    pub fn set_output_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        output::set_file(path.as_ref())
+    }
+
+    /// Set the source of the current time used by all Behold instances for timestamps, rate
+    /// limits, timers, `when_between` and `set_context_ttl`, so that time-dependent behaviour
+    /// can be tested deterministically with a `TestClock`. Set `SystemClock` to use the real
+    /// time again.
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use behold::{behold, SystemClock, TestClock};
+    /// let clock = TestClock::new();
+    /// behold().set_clock(clock.clone());
+    /// behold().set_context_ttl("do-it", true, Duration::from_secs(60));
+    /// clock.advance(Duration::from_secs(59));
+    /// assert_eq!(behold().resolve_context("do-it").map(|(value, _)| value), Some(true));
+    /// clock.advance(Duration::from_secs(1));
+    /// assert_eq!(behold().resolve_context("do-it"), None);
+    /// behold().set_clock(SystemClock);
+    /// ```
+    pub fn set_clock<C: Clock + 'static>(&self, clock: C) {
+        clock::set(Arc::new(clock));
+    }
+
+    /// Hand each line of output bound for stdout to the function instead of writing it, so that
+    /// output can be printed above a progress bar rather than corrupting it. Output redirected
+    /// to a file by `set_output_file` isn't intercepted.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_output_interceptor(|line| eprintln!("[progress bar] {}", line));
+    /// behold().show("Hello world!".to_string());
+    /// behold().clear_output_interceptor();
+    /// ```
+    /// Will produce the output, on stderr:
+    /// ```ignore
+    /// [progress bar] Hello world!
+    /// ```
+    /// With indicatif, pass `move |line| bar.println(line)` for a clone of the progress bar.
+    pub fn set_output_interceptor<F>(&self, interceptor: F)
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        output::set_interceptor(Some(Box::new(interceptor)));
+    }
+
+    /// Write output to stdout again, rather than to the function set with
+    /// `set_output_interceptor`
+    pub fn clear_output_interceptor(&self) {
+        output::set_interceptor(None);
+    }
+
+    /// Render every message shown by any Behold instance with the function, in place of the
+    /// format set with `set_format`. Output to the systemd journal, logcat and OpenTelemetry
+    /// stays structured.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Record};
+    /// behold().set_formatter(|record: &Record| {
+    ///     format!("[{}] {}", record.tag.as_deref().unwrap_or("-"), record.msg)
+    /// });
+    /// behold().tag("net").show("Hello world!".to_string());
+    /// behold().clear_formatter();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// [net] Hello world!
+    /// ```
+    pub fn set_formatter<F>(&self, formatter: F)
+    where
+        F: Fn(&Record) -> String + Send + Sync + 'static,
+    {
+        output::set_formatter(Some(Box::new(formatter)));
+    }
+
+    /// Render messages in the format set with `set_format` again, rather than with the
+    /// function set with `set_formatter`
+    pub fn clear_formatter(&self) {
+        output::set_formatter(None);
+    }
+
+    /// Hand every message shown by any Behold instance to the sink as well as the usual output,
+    /// after any sinks added before it - see `Sink`. Sinks mustn't show output themselves.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Record};
+    /// use std::sync::mpsc;
+    /// use std::sync::Mutex;
+    /// let (sender, receiver) = mpsc::channel();
+    /// let sender = Mutex::new(sender);
+    /// behold().add_sink(move |record: &Record| {
+    ///     let _ = sender.lock().unwrap().send(record.clone());
+    /// });
+    /// behold().show("Hello world!".to_string());
+    /// behold().clear_sinks();
+    /// assert_eq!(receiver.recv().unwrap().msg, "Hello world!");
+    /// ```
+    pub fn add_sink<S: Sink + 'static>(&self, sink: S) {
+        sink::add(Box::new(sink));
+    }
+
+    /// Remove every sink added with `add_sink`
+    pub fn clear_sinks(&self) {
+        sink::clear();
+    }
+
+    /// Pass every message shown by any Behold instance through the middleware before it is
+    /// rendered, after any middleware added before it. Middleware sees the message before it is
+    /// truncated or redacted, and can change it or drop it altogether - see `Middleware`.
+    /// Middleware mustn't show output itself.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Record};
+    /// behold().add_middleware(|record: Record| {
+    ///     if record.tag.as_deref() == Some("noisy") {
+    ///         None
+    ///     } else {
+    ///         Some(record)
+    ///     }
+    /// });
+    /// behold().tag("noisy").show("Dropped".to_string());
+    /// behold().tag("net").show("Hello world!".to_string());
+    /// behold().clear_middleware();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!, net"
+    /// ```
+    pub fn add_middleware<M: Middleware + 'static>(&self, middleware: M) {
+        middleware::add(Box::new(middleware));
+    }
+
+    /// Remove all middleware added with `add_middleware`
+    pub fn clear_middleware(&self) {
+        middleware::clear();
+    }
+
+    /// Forward the output of all Behold instances to an OpenTelemetry logger as well, as log
+    /// records with the tag, location and fields as attributes
+    /// # Examples
+    /// ```
+    /// # extern crate behold;
+    /// # extern crate opentelemetry;
+    /// # fn main() {
+    /// use behold::behold;
+    /// use opentelemetry::logs::{LoggerProvider, NoopLoggerProvider};
+    /// let provider = NoopLoggerProvider::new();
+    /// behold().set_otel_logger(provider.logger("behold"));
+    /// behold().tag("net").show("Hello world!".to_string());
+    /// # }
+    /// ```
+    #[cfg(feature = "otel")]
+    pub fn set_otel_logger<L>(&self, logger: L)
+    where
+        L: opentelemetry::logs::Logger + Send + Sync + 'static,
+    {
+        otel::set_logger(logger);
+    }
+
+    /// Return a Behold instance that appends output with the specified tag
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().tag("apples").show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!, apples"
+    /// ```
+    pub fn tag(&self, tag: &str) -> Self {
+        Behold {
+            tag: Some(tag.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Return a Behold instance that marks output with the severity level, rendered ahead of
+    /// the message in the colors set with `set_theme`
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Level};
+    /// behold().level(Level::Warn).show("Disk almost full".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "WARN Disk almost full"
+    /// ```
+    pub fn level(&self, level: Level) -> Self {
+        Behold {
+            level: Some(level),
+            ..self.clone()
+        }
+    }
+
+    /// Return a Behold instance that prepends output with the specified prefix
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().prefix("worker-1").show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "worker-1: Hello world!"
+    /// ```
+    pub fn prefix(&self, prefix: &str) -> Self {
+        Behold {
+            prefix: Some(prefix.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Tag every Behold instance created on this thread until the returned guard is dropped
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// {
+    ///     let _scope = behold().push_tag_scope("retry-loop");
+    ///     behold().show("Hello world!".to_string());
+    /// }
+    /// behold().show("Goodbye world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!, retry-loop"
+    /// "Goodbye world!"
+    /// ```
+    pub fn push_tag_scope(&self, tag: &str) -> TagScope {
+        TagScope::push(tag)
+    }
+
+    /// Attach the correlation ID to the output of every Behold instance created on this thread
+    /// until the returned guard is dropped, so that all messages for one request can be grepped
+    /// together across modules
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// fn handle(request_id: u64) {
+    ///     let _id = behold().push_correlation_id(request_id);
+    ///     behold().tag("auth").show("Checking credentials".to_string());
+    ///     behold().tag("db").show("Loading user".to_string());
+    /// }
+    /// handle(17);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Checking credentials correlation_id=17, auth"
+    /// "Loading user correlation_id=17, db"
+    /// ```
+    pub fn push_correlation_id<D: Display>(&self, id: D) -> CorrelationScope {
+        CorrelationScope::push(id.to_string())
+    }
+
+    /// Enter a labelled scope, increasing the nesting depth on this thread until the returned
+    /// guard is dropped
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// fn fib(n: u64) -> u64 {
+    ///     let _scope = behold().scope("fib");
+    ///     behold().when_depth_le(2).show(format!("fib({})", n));
+    ///     if n < 2 { n } else { fib(n - 1) + fib(n - 2) }
+    /// }
+    /// fib(10);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "fib(10)"
+    /// "fib(9)"
+    /// "fib(8)"
+    /// ```
+    pub fn scope(&self, label: &str) -> Scope {
+        Scope::enter(label)
+    }
+
+    /// Hold the output of every Behold instance on this thread until the returned guard is
+    /// dropped, then discard it - unless the scope ends by panicking, or `flush` or
+    /// `flush_on_error` is called first, in which case the output held so far is written as
+    /// shown. This prints the trace of an operation only when something went wrong. Output held
+    /// by a nested scope which is flushed passes to the scope enclosing it.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// fn import(rows: &[&str]) -> Result<usize, String> {
+    ///     let buffered = behold().buffered_scope();
+    ///     for row in rows {
+    ///         behold().show(format!("Parsing {}", row));
+    ///     }
+    ///     let result = match rows.iter().position(|row| row.is_empty()) {
+    ///         Some(index) => Err(format!("row {} is empty", index)),
+    ///         None => Ok(rows.len()),
+    ///     };
+    ///     buffered.flush_on_error(result)
+    /// }
+    /// assert_eq!(import(&["a", "b"]), Ok(2));
+    /// assert!(import(&["c", ""]).is_err());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Parsing c"
+    /// "Parsing "
+    /// ```
+    pub fn buffered_scope(&self) -> BufferedScope {
+        BufferedScope::enter()
+    }
+
+    /// Start a timer which, when the returned guard is dropped, shows how long it was alive for
+    /// if this behold instance is configured to speak up
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// {
+    ///     let _timer = behold().tag("startup").time("load config");
+    ///     // ... load the config ...
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "load config took 1.234567ms, startup"
+    /// ```
+    pub fn time(&self, label: &str) -> Timer {
+        let behold = if self.is_speaking() {
+            Some(self.clone())
+        } else {
+            None
+        };
+        Timer::start(behold, label)
+    }
+
+    /// Wrap a value so that a message giving the label and the value's `Debug` representation
+    /// is shown when it's dropped, if this behold instance is configured to speak up - for
+    /// tracking resource lifetimes and accidental early drops. The wrapper derefs to the value.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let connection = behold().tag("db").on_drop(vec![1, 2, 3], "connection");
+    /// assert_eq!(connection.len(), 3);
+    /// drop(connection);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "connection dropped: [1, 2, 3], db"
+    /// ```
+    pub fn on_drop<T: Debug>(&self, value: T, label: &str) -> OnDrop<T> {
+        let behold = if self.is_speaking() {
+            Some(self.clone())
+        } else {
+            None
+        };
+        OnDrop::new(behold, value, label, Some(on_drop::debug::<T>))
+    }
+
+    /// Wrap a value so that a message giving the label is shown when it's dropped, as for
+    /// `on_drop` but for values which don't implement `Debug`
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// struct Connection;
+    /// let connection = behold().on_drop_opaque(Connection, "connection");
+    /// drop(connection);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "connection dropped"
+    /// ```
+    pub fn on_drop_opaque<T>(&self, value: T, label: &str) -> OnDrop<T> {
+        let behold = if self.is_speaking() {
+            Some(self.clone())
+        } else {
+            None
+        };
+        OnDrop::new(behold, value, label, None)
+    }
+
+    /// Start counting the allocations made on this thread, showing how many were made and how
+    /// many bytes they requested when the returned guard is dropped, if this behold instance is
+    /// configured to speak up. Reallocations are counted separately, with only the bytes they
+    /// grow by. Allocations are only counted once `CountingAllocator` has been
+    /// made the global allocator.
+    /// # Examples
+    /// ```
+    /// use std::alloc::System;
+    /// use behold::{behold, CountingAllocator};
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: CountingAllocator<System> = CountingAllocator::new(System);
+    ///
+    /// fn main() {
+    ///     let _count = behold().count_allocs("parse");
+    ///     let words: Vec<String> = "a b c".split(' ').map(str::to_string).collect();
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "parse: 4 allocations, 99 bytes"
+    /// ```
+    /// Growing a buffer is a reallocation:
+    /// ```
+    /// use std::alloc::System;
+    /// use behold::{behold, CountingAllocator};
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: CountingAllocator<System> = CountingAllocator::new(System);
+    ///
+    /// fn main() {
+    ///     let output = behold::test::isolated(|| {
+    ///         let mut buffer = Vec::<u8>::with_capacity(8);
+    ///         let count = behold().count_allocs("grow");
+    ///         buffer.reserve_exact(16);
+    ///         drop(count);
+    ///         behold::test::output()
+    ///     });
+    ///     assert_eq!(output, vec!["grow: 0 allocations, 1 reallocation, 8 bytes"]);
+    /// }
+    /// ```
+    pub fn count_allocs(&self, label: &str) -> AllocCount {
+        let behold = if self.is_speaking() {
+            Some(self.clone())
+        } else {
+            None
+        };
+        AllocCount::start(behold, label)
+    }
+
+    /// Show a message on entry to a function and another, with the time taken, when the
+    /// returned guard is dropped on exit, if this behold instance is configured to speak up.
+    /// `#[behold::trace_impl]` adds this to every method of an `impl` block.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// fn parse(input: &str) -> usize {
+    ///     let _trace = behold().tag("parser").trace_call("parse");
+    ///     input.len()
+    /// }
+    /// parse("Hello world!");
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "-> parse, parser"
+    /// "<- parse (1.2µs), parser"
+    /// ```
+    pub fn trace_call(&self, name: &str) -> TraceCall {
+        let behold = if self.is_speaking() {
+            Some(self.clone())
+        } else {
+            None
+        };
+        TraceCall::enter(behold, name)
+    }
+
+    /// Print how many times each timer started with `time` has finished, with the total time
+    /// taken and the time spent in the timer itself rather than in timers nested within it,
+    /// if this behold instance is configured to speak up. Timers nested within others are
+    /// reported beneath them.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// {
+    ///     let _request = behold().time("request");
+    ///     for _ in 0..3 {
+    ///         let _query = behold().time("query");
+    ///     }
+    /// }
+    /// behold().timing_report();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "query took 300µs"
+    /// "query took 300µs"
+    /// "query took 300µs"
+    /// "request took 1.2ms"
+    /// timing report:
+    ///   request: 1 call, 1.2ms total, 300µs self
+    ///     query: 3 calls, 900µs total, 900µs self
+    /// ```
+    pub fn timing_report(&self) -> &Self {
+        if self.speaking() {
+            self.emit(&timer::report());
+        }
+        self
+    }
+
+    /// Record a value under the label if this behold instance is configured to speak up, for
+    /// `stats_report` to summarize - metrics without a metrics crate
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// for batch in &[vec![1, 2], vec![3], vec![4, 5, 6]] {
+    ///     behold().observe("batch_size", batch.len() as u32);
+    /// }
+    /// ```
+    pub fn observe<T: Into<f64>>(&self, label: &str, value: T) -> &Self {
+        if self.is_speaking() {
+            stats::observe(label, value.into());
+        }
+        self
+    }
+
+    /// Print the count, min, max, mean and 50th, 90th and 99th percentiles of the values
+    /// recorded under each label with `observe`, if this behold instance is configured to speak
+    /// up
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// for latency in &[12, 7, 9, 31, 8] {
+    ///     behold().observe("latency_ms", *latency);
+    /// }
+    /// behold().stats_report();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// stats report:
+    ///   latency_ms: 5 values, min 7, max 31, mean 13.4, p50 9, p90 31, p99 31
+    /// ```
+    pub fn stats_report(&self) -> &Self {
+        if self.speaking() {
+            self.emit(&stats::report());
+        }
+        self
+    }
+
+    /// Count an event under the label if this behold instance is configured to speak up, for
+    /// `show_rates` or `report_rates_every` to report as events per second
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// for _ in 0..3 {
+    ///     behold().tick("requests");
+    /// }
+    /// ```
+    pub fn tick(&self, label: &str) -> &Self {
+        if self.is_speaking() {
+            rate::tick(label);
+        }
+        self
+    }
+
+    /// Print the events per second counted by `tick` for each label since the first event or
+    /// the last report, whichever was later, if this behold instance is configured to speak up,
+    /// starting a new window
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// for _ in 0..20 {
+    ///     behold().tick("requests");
+    ///     thread::sleep(Duration::from_millis(5));
+    /// }
+    /// behold().show_rates();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// rates over 100.8ms:
+    ///   requests: 20 (198.4/s)
+    /// ```
+    pub fn show_rates(&self) -> &Self {
+        if self.speaking() {
+            self.emit(&rate::report());
+        }
+        self
+    }
+
+    /// Print a bar chart of the values recorded under the label with `observe` if this behold
+    /// instance is configured to speak up, counting them in ten equal width buckets between
+    /// the smallest and largest, so that their distribution can be seen at a glance
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// for latency in &[1, 2, 2, 3, 3, 3, 4, 4, 5, 11] {
+    ///     behold().observe("latency_ms", *latency);
+    /// }
+    /// behold().show_histogram("latency_ms");
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// latency_ms histogram:
+    ///     1 - 2 | ############# 1
+    ///     2 - 3 | ########################## 2
+    ///     3 - 4 | ######################################## 3
+    ///     4 - 5 | ########################## 2
+    ///     5 - 6 | ############# 1
+    ///     6 - 7 | 0
+    ///     7 - 8 | 0
+    ///     8 - 9 | 0
+    ///    9 - 10 | 0
+    ///   10 - 11 | ############# 1
+    /// ```
+    pub fn show_histogram(&self, label: &str) -> &Self {
+        if self.speaking() {
+            self.emit(&stats::histogram(label));
+        }
+        self
+    }
+
+    /// Record the beginning and end of every timer started with `time` as Chrome trace events
+    /// until the returned guard is dropped, at which point they are written to the specified
+    /// file for viewing in chrome://tracing or Perfetto. Timers are categorized by tag.
+    /// # Examples
+    /// ```no_run
+    /// use behold::behold;
+    /// fn main() {
+    ///     let _trace = behold().record_trace("trace.json");
+    ///     let _timer = behold().time("main");
+    ///     // ... do the work being traced ...
+    /// }
+    /// ```
+    pub fn record_trace<P: AsRef<Path>>(&self, path: P) -> TraceFile {
+        TraceFile::start(path.as_ref())
+    }
+
+    /// Append a folded stack line, such as `main;parse;lex 1234`, to the specified file as each
+    /// guard returned by `scope` or `time` is dropped - giving the labels of the enclosing
+    /// guards and the microseconds spent in the guard itself rather than those nested within it.
+    /// Render the file with `inferno-flamegraph` or `flamegraph.pl`.
+    /// # Examples
+    /// ```no_run
+    /// use behold::behold;
+    /// behold().set_folded_stack_file("stacks.folded").unwrap();
+    /// let _main = behold().scope("main");
+    /// let _parse = behold().time("parse");
+    /// ```
+    pub fn set_folded_stack_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        folded::set_file(path.as_ref())
+    }
+
+    /// Return a Behold instance with a context key of its own, overriding thread-local and
+    /// global context for this instance and those derived from it
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold()
+    ///     .local_context("do-it", true)
+    ///     .when_context("do-it")
+    ///     .show("Hello world!".to_string());
+    /// behold().when_context("do-it").show("Goodbye world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    pub fn local_context(&self, key: &str, value: bool) -> Self {
+        let mut local_context = self.local_context.clone();
+        local_context.insert(key.to_string(), value);
+        Behold {
+            local_context,
+            ..self.clone()
+        }
+    }
+
+    /// Return a Behold instance that attaches the specified key-value pair to output
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().field("user", 42).show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world! user=42"
+    /// ```
+    pub fn field<V: Display>(&self, key: &str, value: V) -> Self {
+        let mut fields = self.fields.clone();
+        fields.push((key.to_string(), value.to_string()));
+        Behold {
+            fields,
+            ..self.clone()
+        }
+    }
+
+    /// Return a Behold instance that attaches the correlation ID to output, in place of any
+    /// pushed with `push_correlation_id`
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().with_correlation_id("req-17").show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world! correlation_id=req-17"
+    /// ```
+    pub fn with_correlation_id<D: Display>(&self, id: D) -> Self {
+        Behold {
+            correlation_id: Some(id.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Return a Behold instance that reports the specified source location with its output.
+    ///
+    /// This is usually called via the `behold!` macro, which captures the location of its caller.
+    /// # Examples
+    /// ```
+    /// #[macro_use]
+    /// extern crate behold;
+    ///
+    /// fn main() {
+    ///     behold!().show("Hello world!".to_string());
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "src/main.rs:5 Hello world!"
+    /// ```
+    pub fn location(&self, location: Location) -> Self {
+        Behold {
+            location: Some(location),
+            ..self.clone()
+        }
+    }
+
+    /// Return a Behold instance created at the call site, registering the site with
+    /// `call_sites` - used by `behold!`
+    #[doc(hidden)]
+    pub fn site(&self, site: &'static Site) -> Self {
+        site.register();
+        Behold {
+            location: Some(site.location()),
+            site: Some(site),
+            ..self.clone()
+        }
+    }
+
+    /// Return a Behold instance that truncates messages longer than `max` bytes, regardless of
+    /// the global maximum set by `set_max_length`
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().max_length(5).show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello… (+7 bytes)"
+    /// ```
+    pub fn max_length(&self, max: usize) -> Self {
+        Behold {
+            max_length: Some(max),
+            ..self.clone()
+        }
+    }
+
+    /// Return a Behold instance that shows at most `messages` messages per interval.
+    ///
+    /// The limit is shared by all rate limited instances with the same tag. A notice reports
+    /// how many messages were dropped once they're allowed through again, or once an interval
+    /// has passed since the first was dropped, whichever comes first - and `shutdown` reports
+    /// any dropped since.
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use behold::behold;
+    /// for i in 0..1000 {
+    ///     behold().tag("loop").rate_limit(2, Duration::from_secs(1)).show(format!("Iteration {}", i));
+    /// }
+    /// behold::shutdown();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Iteration 0, loop"
+    /// "Iteration 1, loop"
+    /// "dropped 998 messages, loop"
+    /// ```
+    /// The notice at exit can be checked:
+    /// ```
+    /// use std::time::Duration;
+    /// use behold::behold;
+    /// let output = behold::test::isolated(|| {
+    ///     for i in 0..5 {
+    ///         behold().rate_limit(1, Duration::from_secs(60)).show(i.to_string());
+    ///     }
+    ///     behold::shutdown();
+    ///     behold::test::output()
+    /// });
+    /// assert_eq!(output, vec!["0", "dropped 4 messages"]);
+    /// ```
+    pub fn rate_limit(&self, messages: u32, per: Duration) -> Self {
+        Behold {
+            rate_limit: Some(RateLimit { messages, per }),
+            ..self.clone()
+        }
+    }
+
+    /// Produce a behold instance which can speak up or not, depending on the parameter
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().when(0 % 2 == 1).show("Hello world!".to_string());
+    /// ```
+    /// Will output nothing.
+    /// ```
+    /// use behold::behold;
+    /// behold().when(0 % 2 == 0).show("Hello world!".to_string());
+    /// ```
+    /// Will output
+    /// ```ignore
+    /// "Hello world!, apples"
+    /// ```
+    pub fn when(&self, speak_up: bool) -> Self {
+        Behold {
+            speak_up,
+            ..self.clone()
+        }
+    }
+
+    /// Produce a behold instance which speaks up only the first time its call site would have,
+    /// so that diagnostics inside loops or frequently called functions appear just once. Call
+    /// sites are distinguished for instances created with `behold!`; other instances are
+    /// counted by tag. Apply other conditionals such as `when_context` first.
+    /// # Examples
+    /// ```
+    /// #[macro_use]
+    /// extern crate behold;
+    ///
+    /// fn main() {
+    ///     for i in 0..3 {
+    ///         behold!().once().show(format!("Iteration {}", i));
+    ///     }
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "src/main.rs:6 Iteration 0"
+    /// ```
+    pub fn once(&self) -> Self {
+        self.first_n(1)
+    }
+
+    /// Produce a behold instance which speaks up only the first `n` times its call site would
+    /// have, as for `once`
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// for i in 0..10 {
+    ///     behold().tag("loop").first_n(2).show(format!("Iteration {}", i));
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Iteration 0, loop"
+    /// "Iteration 1, loop"
+    /// ```
+    pub fn first_n(&self, n: u64) -> Self {
+        if !self.is_speaking() {
+            return self.clone();
+        }
+        let site = once::site(self.location.as_ref(), self.tag.as_deref());
+        self.when(once::admit(site, n))
+    }
+
+    /// Produce a behold instance which speaks up only in debug builds (when `debug_assertions` is enabled)
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().when_debug_build().show("Hello world!".to_string());
+    /// ```
+    /// Will output
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    /// in a debug build and nothing in a release build.
+    pub fn when_debug_build(&self) -> Self {
+        self.when(cfg!(debug_assertions))
+    }
+
+    /// Produce a behold instance which speaks up only when nested at most `depth` scopes deep on
+    /// this thread, as counted by the guards returned from `scope`
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let _outer = behold().scope("outer");
+    /// behold().when_depth_le(1).show("Hello world!".to_string());
+    /// let _inner = behold().scope("inner");
+    /// behold().when_depth_le(1).show("Goodbye world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    pub fn when_depth_le(&self, depth: usize) -> Self {
+        self.when(scope::depth() <= depth)
+    }
+
+    /// Produce a behold instance which speaks up only on threads whose name matches the glob
+    /// pattern, in which `*` matches any run of characters and `?` matches exactly one.
+    /// Unnamed threads never match.
+    /// # Examples
+    /// ```
+    /// use std::thread;
+    /// use behold::behold;
+    /// let workers: Vec<_> = (0..4)
+    ///     .map(|i| {
+    ///         thread::Builder::new()
+    ///             .name(format!("worker-{}", i))
+    ///             .spawn(move || {
+    ///                 behold().when_thread("worker-3").show(format!("Hello from {}!", i));
+    ///             })
+    ///             .unwrap()
+    ///     })
+    ///     .collect();
+    /// for worker in workers {
+    ///     worker.join().unwrap();
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello from 3!"
+    /// ```
+    pub fn when_thread(&self, pattern: &str) -> Self {
+        let speak_up = match thread::current().name() {
+            Some(name) => filter::glob(pattern, name),
+            None => false,
+        };
+        self.when(speak_up)
+    }
+
+    /// Produce a behold instance which speaks up only on the main thread - the thread running
+    /// `main`, which the standard library names `main` - such as the UI thread of an app whose
+    /// workers run the same code
+    /// # Examples
+    /// ```
+    /// use std::thread;
+    /// use behold::behold;
+    /// fn render() {
+    ///     behold().when_thread_main().show("Rendering".to_string());
+    /// }
+    /// # fn main() {
+    /// render();
+    /// thread::spawn(render).join().unwrap();
+    /// # }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Rendering"
+    /// ```
+    pub fn when_thread_main(&self) -> Self {
+        self.when(is_main_thread())
+    }
+
+    /// Produce a behold instance which speaks up only off the main thread, as determined by
+    /// `when_thread_main`
+    /// # Examples
+    /// ```
+    /// use std::thread;
+    /// use behold::behold;
+    /// fn render() {
+    ///     behold().when_thread_not_main().show("Rendering".to_string());
+    /// }
+    /// # fn main() {
+    /// render();
+    /// thread::spawn(render).join().unwrap();
+    /// # }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Rendering"
+    /// ```
+    pub fn when_thread_not_main(&self) -> Self {
+        self.when(!is_main_thread())
+    }
+
+    /// Produce a behold instance which speaks up only from `start` up until (but not including)
+    /// `end`, given either as `Instant`s or as wall-clock `SystemTime`s
+    /// # Examples
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use behold::behold;
+    /// let startup = Instant::now();
+    /// behold()
+    ///     .when_between(startup, startup + Duration::from_secs(10))
+    ///     .show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    /// but only during the first 10 seconds after startup.
+    pub fn when_between<T: Moment>(&self, start: T, end: T) -> Self {
+        let now = T::now();
+        self.when(start <= now && now < end)
+    }
+
+    /// Produce a behold instance which can speak up or not, depending on the specified context
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().when_context("do-it").show("Hello world!".to_string());
+    /// ```
+    /// Will output nothing.
+    /// ```
+    /// use behold::behold;
+    /// behold().set_context("do-it", true);
+    /// behold().when_context("do-it").show("Hello world!".to_string());
+    /// ```
+    /// Will output
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    ///
+    /// The key is resolved from instance-local context, then thread-local context, then global
+    /// context, as described by `ContextLayer`; missing keys are treated as `false`.
+    ///
+    /// If no layer holds the key and the instance was created by `behold!`, keys of the form
+    /// `myapp::net::*` are matched against the caller's module path instead, so that output can
+    /// be enabled for a whole module tree without a key per call site. The most specific pattern
+    /// in the first layer holding one wins.
+    /// ```
+    /// #[macro_use]
+    /// extern crate behold;
+    /// use behold::behold;
+    ///
+    /// fn main() {
+    ///     behold().set_context("rust_out::*", true);
+    ///     behold!().when_context("parser").show("Hello world!".to_string());
+    ///     behold().set_context("rust_out::*", false);
+    /// }
+    /// ```
+    /// Will output
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    pub fn when_context(&self, key: &str) -> Self {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return self.when(false);
+        }
+        // Most keys are missing or disabled, which the summaries can usually tell without
+        // locking - but a disabled key overrides module patterns, so if module patterns could
+        // apply a key which might be present must be looked up
+        let speak_up = match self.resolve_unlocked(key) {
+            Some((value, _)) => value,
+            None if self.context.might_be_enabled(key)
+                || (self.location.is_some() && self.context.might_be_present(key)) =>
+            {
+                match self.resolve_shared(key) {
+                    Some(value) => value,
+                    None => self.resolve_module() == Some(true),
+                }
+            }
+            None => self.resolve_module() == Some(true),
+        };
+        Behold {
+            speak_up,
+            ..self.clone()
+        }
+    }
+
+    /// Produce a behold instance which speaks up only when the counter incremented by
+    /// `increment_context_counter` has reached exactly the count, so that something happening
+    /// for the 1000th time can be shown without showing the 999 times before it. Use
+    /// `when_context_ge` to keep speaking up afterwards.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// for attempt in 1..=5 {
+    ///     behold().increment_context_counter("retries");
+    ///     behold()
+    ///         .when_count_reaches("retries", 3)
+    ///         .show(format!("Still retrying after {} attempts", attempt));
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Still retrying after 3 attempts"
+    /// ```
+    pub fn when_count_reaches(&self, key: &str, count: u64) -> Self {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return self.when(false);
+        }
+        self.when(self.context_level(key) == Some(count))
+    }
+
+    /// Produce a behold instance which speaks up only if the level set for the key with
+    /// `set_context_level` is at least the threshold; keys without a level are treated as 0
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_context_level("net.verbosity", 1);
+    /// behold()
+    ///     .when_context_ge("net.verbosity", 1)
+    ///     .show("Connected".to_string());
+    /// behold()
+    ///     .when_context_ge("net.verbosity", 2)
+    ///     .show("Sent 12 bytes".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Connected"
+    /// ```
+    pub fn when_context_ge(&self, key: &str, threshold: u64) -> Self {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return self.when(false);
+        }
+        self.when(self.context_level(key).unwrap_or(0) >= threshold)
+    }
+
+    /// Produce a behold instance which speaks up only if the level set for the key with
+    /// `set_context_level` is at most the threshold; keys without a level never speak up
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_context_level("net.verbosity", 1);
+    /// behold()
+    ///     .when_context_le("net.verbosity", 1)
+    ///     .show("Connected".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Connected"
+    /// ```
+    pub fn when_context_le(&self, key: &str, threshold: u64) -> Self {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return self.when(false);
+        }
+        self.when(
+            self.context_level(key)
+                .is_some_and(|level| level <= threshold),
+        )
+    }
+
+    /// Look up the value `when_context` would use for a key, along with the layer of context it
+    /// came from, or None if no layer holds the key
+    /// # Examples
+    /// ```
+    /// use behold::{behold, ContextLayer};
+    /// behold().set_context("do-it", true);
+    /// behold().set_thread_context("do-it", false);
+    /// assert_eq!(behold().resolve_context("do-it"), Some((false, ContextLayer::Thread)));
+    /// assert_eq!(
+    ///     behold().local_context("do-it", true).resolve_context("do-it"),
+    ///     Some((true, ContextLayer::Instance))
+    /// );
+    /// assert_eq!(behold().resolve_context("dont"), None);
+    /// ```
+    pub fn resolve_context(&self, key: &str) -> Option<(bool, ContextLayer)> {
+        self.resolve_unlocked(key).or_else(|| {
+            self.resolve_shared(key)
+                .map(|value| (value, ContextLayer::Global))
+        })
+    }
+
+    /// Look up a key in the layers of context which can be read without locking
+    fn resolve_unlocked(&self, key: &str) -> Option<(bool, ContextLayer)> {
+        if let Some(value) = self.local_context.get(key) {
+            return Some((*value, ContextLayer::Instance));
+        }
+        if let Some(value) = context::get_thread(key) {
+            return Some((value, ContextLayer::Thread));
+        }
+        #[cfg(all(feature = "shared-memory", unix))]
+        {
+            if let Some(value) = shared::get(key).filter(|_| self.uses_global_context()) {
+                return Some((value, ContextLayer::Global));
+            }
+        }
+        None
+    }
+
+    /// Look up the module patterns matching the module path of this instance's location, if it
+    /// has one
+    fn resolve_module(&self) -> Option<bool> {
+        let module_path = self.location.as_ref()?.module_path;
+        if let Some(value) = context::match_module(&self.local_context, module_path) {
+            return Some(value);
+        }
+        if let Some(value) = context::match_thread_module(module_path) {
+            return Some(value);
+        }
+        if !self.context.might_match_module() {
+            return None;
+        }
+        match self.context.lock() {
+            Ok(mut context) => {
+                self.context.expire(&mut context);
+                context::match_module(&context, module_path)
+            }
+            Err(err) => {
+                panic!(
+                    "when_context called on an instance of Behold - mutex already acquired - {:?}!",
+                    err
+                );
+            }
+        }
+    }
+
+    /// Look up a key in the context shared with other instances
+    fn resolve_shared(&self, key: &str) -> Option<bool> {
+        match self.context.lock() {
+            Ok(mut context) => {
+                self.context.expire(&mut context);
+                context.get(key).cloned()
+            }
+            Err(err) => {
+                panic!(
+                    "when_context called on an instance of Behold - mutex already acquired - {:?}!",
+                    err
+                );
+            }
+        }
+    }
+
+    /// A copy of every context key visible to this instance and the value `when_context` would
+    /// use for it, merging instance-local, thread-local and global context
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_context("do-it", true);
+    /// behold().set_thread_context("do-it", false);
+    /// let snapshot = behold().local_context("dont", false).context_snapshot();
+    /// assert_eq!(snapshot.get("do-it"), Some(&false));
+    /// assert_eq!(snapshot.get("dont"), Some(&false));
+    /// ```
+    pub fn context_snapshot(&self) -> BTreeMap<String, bool> {
+        let mut snapshot = match self.context.lock() {
+            Ok(mut context) => {
+                self.context.expire(&mut context);
+                context.clone()
+            }
+            Err(err) => panic!(
+                "context_snapshot called on an instance of Behold - mutex already acquired - {:?}!",
+                err
+            ),
+        };
+        snapshot.extend(context::thread_snapshot());
+        snapshot.extend(
+            self.local_context
+                .iter()
+                .map(|(key, value)| (key.clone(), *value)),
+        );
+        snapshot
+    }
+
+    /// The context keys visible to this instance which start with the prefix, and the value
+    /// `when_context` would use for each, so that related keys can be listed and toggled as a
+    /// group
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().set_context("net.tcp", true);
+    /// behold().set_context("net.udp", false);
+    /// behold().set_context("parser", true);
+    /// let net = behold().context_keys_with_prefix("net.");
+    /// assert_eq!(net.keys().collect::<Vec<_>>(), vec!["net.tcp", "net.udp"]);
+    /// for key in net.keys() {
+    ///     behold().set_context(key, false);
+    /// }
+    /// ```
+    pub fn context_keys_with_prefix(&self, prefix: &str) -> BTreeMap<String, bool> {
+        self.context_snapshot()
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .collect()
+    }
+
+    /// Print the provided string if this behold instance is configured to speak up
+    ///
+    /// Returns the instance so that several related messages can be shown in one chain.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().show("Hello world!".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    /// Whereas the following:
+    /// ```
+    /// use behold::behold;
+    /// behold().tag("io").show("Opening file".to_string()).show("Reading file".to_string());
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Opening file, io"
+    /// "Reading file, io"
+    /// ```
+    pub fn show(&self, msg: String) -> &Self {
+        if self.speaking() {
+            self.emit(&msg);
+        }
+        self
+    }
+
+    /// Print the formatted arguments, if this behold instance is configured to speak up. Unlike
+    /// `show(format!(...))` nothing is formatted when silent, and when speaking the message is
+    /// formatted into a buffer reused by each thread rather than a new `String`, so hot paths
+    /// don't allocate for every message. Messages without arguments aren't copied at all.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let (x, y) = (3, 4);
+    /// behold().show_fmt(format_args!("x = {}, y = {}", x, y));
+    /// behold().when(false).show_fmt(format_args!("{:?}", vec![x; 1000]));
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "x = 3, y = 4"
+    /// ```
+    pub fn show_fmt(&self, args: fmt::Arguments) -> &Self {
+        if !self.speaking() {
+            return self;
+        }
+        if let Some(msg) = args.as_str() {
+            self.emit(msg);
+            return self;
+        }
+        let reused = FORMATTED.try_with(|buffer| match buffer.try_borrow_mut() {
+            Ok(mut buffer) => {
+                buffer.clear();
+                let _ = buffer.write_fmt(args);
+                self.emit(&buffer);
+                true
+            }
+            // Showing a message while formatting another, such as from a `Display` impl
+            Err(_) => false,
+        });
+        if reused != Ok(true) {
+            self.emit(&fmt::format(args));
+        }
+        self
+    }
+
+    /// Print the event's name with its fields attached, if this behold instance is configured
+    /// to speak up - see `BeholdEvent`
+    /// # Examples
+    /// ```
+    /// use behold::{behold, BeholdEvent};
+    /// struct Retrying(u32);
+    ///
+    /// impl BeholdEvent for Retrying {
+    ///     fn name(&self) -> &str {
+    ///         "retrying"
+    ///     }
+    ///
+    ///     fn fields(&self) -> Vec<(String, String)> {
+    ///         vec![("attempt".to_string(), self.0.to_string())]
+    ///     }
+    /// }
+    ///
+    /// behold().tag("net").show_event(&Retrying(2));
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "retrying attempt=2, net"
+    /// ```
+    pub fn show_event<E: BeholdEvent + ?Sized>(&self, event: &E) -> &Self {
+        if self.speaking() {
+            let mut fields = self.fields.clone();
+            fields.extend(event.fields());
+            Behold {
+                fields,
+                ..self.clone()
+            }
+            .emit(event.name());
+        }
+        self
+    }
+
+    /// Print the provided text without ending the line, if this behold instance is configured to
+    /// speak up, so that progress can be shown a little at a time. The text is not tagged or
+    /// formatted, and always goes to stdout or the output file set with `set_output_file`.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let progress = behold().tag("download");
+    /// for _ in 0..5 {
+    ///     progress.show_inline(".");
+    /// }
+    /// progress.endline();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// .....
+    /// ```
+    pub fn show_inline(&self, text: &str) -> &Self {
+        if self.speaking() {
+            match redact::REDACTIONS.read() {
+                Ok(ref redactions) if !redactions.is_empty() => {
+                    output::write_inline(&redactions.redact(text))
+                }
+                _ => output::write_inline(text),
+            }
+        }
+        self
+    }
+
+    /// End the line started by `show_inline`, if this behold instance is configured to speak up
+    pub fn endline(&self) -> &Self {
+        if self.speaking() {
+            output::write_inline("\n");
+        }
+        self
+    }
+
+    /// Write the provided bytes as they are if this behold instance is configured to speak up,
+    /// so that binary protocols can be debugged without a lossy conversion to UTF-8 first.
+    ///
+    /// When the output is a terminal, or text-only such as the systemd journal, the bytes are
+    /// instead shown as a message with anything that isn't printable ASCII escaped - as they
+    /// are whenever anything else needs to see them as a message: a buffered scope, redaction,
+    /// middleware, sinks, duplicate suppression, OpenTelemetry or a session recording.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().tag("wire").show_bytes(b"\x02HELO\r\n");
+    /// ```
+    /// Will produce the output on a terminal:
+    /// ```ignore
+    /// \x02HELO\r\n, wire
+    /// ```
+    /// Bytes are held by buffered scopes, in order with other output, and redacted:
+    /// ```
+    /// use behold::{behold, test};
+    /// behold().redact_field("password");
+    /// test::isolated(|| {
+    ///     let buffered = behold().buffered_scope();
+    ///     behold().show("Logging in".to_string());
+    ///     behold().show_bytes(b"password=hunter2 \x03");
+    ///     assert!(test::output().is_empty());
+    ///     buffered.flush();
+    ///     assert_eq!(test::output(), vec!["Logging in", "password=*** \\x03"]);
+    /// });
+    /// ```
+    pub fn show_bytes(&self, bytes: &[u8]) -> &Self {
+        if self.speaking() && self.admit() && !(raw_bytes_allowed() && output::write_raw(bytes)) {
+            self.write(&format::escape_bytes(bytes));
+        }
+        self
+    }
+
+    /// Print each of the provided items on its own line as a single message, ending with the
+    /// item count as a suffix ahead of the tag, if this behold instance is configured to speak
+    /// up
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().tag("fruit").show_many(vec!["apples", "pears"]);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// - apples
+    /// - pears
+    /// 2 items, fruit
+    /// ```
+    /// The output can be checked:
+    /// ```
+    /// use behold::{behold, test};
+    /// test::isolated(|| {
+    ///     behold().show_many(vec![1]);
+    ///     assert_eq!(test::output(), vec!["- 1", "1 item"]);
+    /// });
+    /// ```
+    pub fn show_many<I>(&self, items: I) -> &Self
+    where
+        I: IntoIterator,
+        I::Item: Display,
+    {
+        if self.speaking() {
+            let mut msg = String::new();
+            let mut count = 0;
+            for item in items {
+                msg.push_str(&format!("- {}\n", item));
+                count += 1;
+            }
+            match count {
+                1 => msg.push_str("1 item"),
+                n => msg.push_str(&format!("{} items", n)),
+            }
+            self.emit(&msg);
+        }
+        self
+    }
+
+    /// Print the length and the first and last `k` items of a slice if this behold instance is
+    /// configured to speak up, rather than dumping every item of a large collection
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let ids: Vec<u32> = (0..10_000).collect();
+    /// behold().tag("ids").show_slice_summary(&ids, 3);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "len 10000: [0, 1, 2, … 9994 more, 9997, 9998, 9999], ids"
+    /// ```
+    pub fn show_slice_summary<T: Debug>(&self, items: &[T], k: usize) -> &Self {
+        if self.speaking() {
+            self.emit(&format::slice_summary(items, k));
+        }
+        self
+    }
+
+    /// Print the length, the first and last `k` items, and the smallest and largest items of a
+    /// slice of numbers - or anything else ordered - if this behold instance is configured to
+    /// speak up. Items which can't be compared, such as NaN, are left out of the min and max.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let latencies = vec![12.5, 3.0, 48.25, 7.5, 9.0, 15.0, 4.5];
+    /// behold().show_numeric_summary(&latencies, 2);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "len 7: [12.5, 3.0, … 3 more, 15.0, 4.5], min 3.0, max 48.25"
+    /// ```
+    pub fn show_numeric_summary<T: Debug + PartialOrd>(&self, items: &[T], k: usize) -> &Self {
+        if self.speaking() {
+            let mut msg = format::slice_summary(items, k);
+            let comparable = items.iter().filter(|item| item.partial_cmp(item).is_some());
+            let min = comparable
+                .clone()
+                .fold(None, |min: Option<&T>, item| match min {
+                    Some(min) if min <= item => Some(min),
+                    _ => Some(item),
+                });
+            let max = comparable.fold(None, |max: Option<&T>, item| match max {
+                Some(max) if max >= item => Some(max),
+                _ => Some(item),
+            });
+            if let (Some(min), Some(max)) = (min, max) {
+                msg.push_str(&format!(", min {:?}, max {:?}", min, max));
+            }
+            self.emit(&msg);
+        }
+        self
+    }
+
+    /// Print the name and `Debug` representation of a value if this behold instance is
+    /// configured to speak up, but only if the representation differs from the one shown last
+    /// time for the same name and location.
+    ///
+    /// This is usually called via the `behold_watch!` macro, which names the value after the
+    /// expression and records the location.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// for i in 0..6 {
+    ///     behold().watch("i / 2", &(i / 2));
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "i / 2 = 0"
+    /// "i / 2 = 1"
+    /// "i / 2 = 2"
+    /// ```
+    pub fn watch<T: Debug>(&self, name: &str, value: &T) -> &Self {
+        if self.speaking() {
+            let rendered = format!("{:?}", value);
+            if watch::changed(self.location.as_ref(), name, &rendered) {
+                self.emit(&format!("{} = {}", name, rendered));
+            }
+        }
+        self
+    }
+
+    /// Print the label and `Debug` representation of a value if this behold instance is
+    /// configured to speak up, but only if the value differs from the one shown last time for
+    /// the same label and location
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// for retries in &[0, 0, 1, 1, 3] {
+    ///     behold().show_if_changed("retries", retries);
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "retries = 0"
+    /// "retries = 1"
+    /// "retries = 3"
+    /// ```
+    pub fn show_if_changed<T>(&self, label: &str, value: &T) -> &Self
+    where
+        T: Clone + Debug + PartialEq + Send + 'static,
+    {
+        self.show_if_changed_by(label, value, |last, value| last == value)
+    }
+
+    /// Print the label and `Debug` representation of a value if this behold instance is
+    /// configured to speak up, but only if `eq` says it differs from the one shown last time for
+    /// the same label and location, so that only meaningful changes are shown
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// #[derive(Clone, Debug)]
+    /// struct Config {
+    ///     version: u32,
+    ///     loaded_at: u64,
+    /// }
+    ///
+    /// for (version, loaded_at) in &[(1, 100), (1, 200), (2, 300)] {
+    ///     let config = Config {
+    ///         version: *version,
+    ///         loaded_at: *loaded_at,
+    ///     };
+    ///     behold().show_if_changed_by("config", &config, |a, b| a.version == b.version);
+    /// }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "config = Config { version: 1, loaded_at: 100 }"
+    /// "config = Config { version: 2, loaded_at: 300 }"
+    /// ```
+    pub fn show_if_changed_by<T, F>(&self, label: &str, value: &T, eq: F) -> &Self
+    where
+        T: Clone + Debug + Send + 'static,
+        F: Fn(&T, &T) -> bool,
+    {
+        if self.speaking() && watch::changed_by(self.location.as_ref(), label, value, eq) {
+            self.emit(&format!("{} = {:?}", label, value));
+        }
+        self
+    }
+
+    /// Print the provided number, of any type including `u64` and `usize`, if this behold
+    /// instance is configured to speak up
+    ///
+    /// Combined with `Format::Csv` this produces `timestamp,tag,value` rows that can be
+    /// piped straight into a spreadsheet or gnuplot.
+    /// # Examples
+    /// ```
+    /// use behold::{behold, Format};
+    /// behold().set_format(Format::Csv);
+    /// for i in 0..3 {
+    ///     behold().tag("squares").show_value(i * i);
+    /// }
+    /// behold().set_format(Format::Plain);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// 1520164800.000,squares,0
+    /// 1520164800.001,squares,1
+    /// 1520164800.002,squares,4
+    /// ```
+    /// Wide integers are shown exactly:
+    /// ```
+    /// use behold::behold;
+    /// let output = behold::test::isolated(|| {
+    ///     behold().show_value(u64::MAX).show_value(-3i64).show_value(7usize);
+    ///     behold::test::output()
+    /// });
+    /// assert_eq!(output, vec!["18446744073709551615", "-3", "7"]);
+    /// ```
+    pub fn show_value<T: Display>(&self, value: T) -> &Self {
+        if self.speaking() {
+            self.emit(&value.to_string());
+        }
+        self
+    }
+
+    /// Trap into the attached debugger if this behold instance is configured to speak up, so
+    /// that a debugger only stops where the context says it should - with x86's `int3`,
+    /// aarch64's `brk` or, on other unix platforms, SIGTRAP. Without a debugger attached the
+    /// process is usually killed by SIGTRAP, or on Windows by an unhandled breakpoint exception.
+    /// # Examples
+    /// ```no_run
+    /// use behold::behold;
+    /// for id in 0..100 {
+    ///     behold().set_context("trap", id == 42);
+    ///     behold().when_context("trap").breakpoint();
+    /// }
+    /// ```
+    #[cfg(feature = "breakpoint")]
+    pub fn breakpoint(&self) -> &Self {
+        if self.speaking() {
+            breakpoint::trap();
+        }
+        self
+    }
+
+    /// Print the memory used by the current process after the provided label, if this behold
+    /// instance is configured to speak up, so that memory growth across phases can be seen
+    /// without a profiler run. Linux reports the current and peak resident set size; other
+    /// unix platforms report only the peak.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let data: Vec<u64> = (0..1_000_000).collect();
+    /// behold().show_mem("loaded");
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "loaded: resident 11.6 MiB, peak 11.6 MiB"
+    /// ```
+    #[cfg(feature = "mem")]
+    pub fn show_mem(&self, label: &str) -> &Self {
+        if self.speaking() {
+            self.emit(&mem::describe(label, &mem::usage()));
+        }
+        self
+    }
+
+    /// Print the provided value as pretty printed JSON if this behold instance is configured to speak up
+    /// # Examples
+    /// ```
+    /// # extern crate behold;
+    /// # extern crate serde_json;
+    /// # fn main() {
+    /// use behold::behold;
+    /// behold().show_json(&serde_json::json!({ "name": "apples", "count": 3 }));
+    /// # }
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// {
+    ///   "count": 3,
+    ///   "name": "apples"
+    /// }
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn show_json<T: serde::Serialize>(&self, value: &T) -> &Self {
+        if self.speaking() {
+            match serde_json::to_string_pretty(value) {
+                Ok(json) => self.emit(&json),
+                Err(err) => self.emit(&format!("failed to serialize as JSON - {}", err)),
+            }
+        }
+        self
+    }
+
+    /// Print the provided value as YAML if this behold instance is configured to speak up
+    /// # Examples
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use behold::behold;
+    /// let mut fruit = BTreeMap::new();
+    /// fruit.insert("apples", 3);
+    /// fruit.insert("pears", 5);
+    /// behold().show_yaml(&fruit);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// apples: 3
+    /// pears: 5
+    /// ```
+    #[cfg(feature = "yaml")]
+    pub fn show_yaml<T: serde::Serialize>(&self, value: &T) -> &Self {
+        if self.speaking() {
+            match serde_yaml::to_string(value) {
+                Ok(yaml) => self.emit(yaml.trim_end()),
+                Err(err) => self.emit(&format!("failed to serialize as YAML - {}", err)),
+            }
+        }
+        self
+    }
+
+    /// Print the provided value as pretty printed RON if this behold instance is configured to speak up
+    /// # Examples
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use behold::behold;
+    /// let mut fruit = BTreeMap::new();
+    /// fruit.insert("apples", 3);
+    /// fruit.insert("pears", 5);
+    /// behold().show_ron(&fruit);
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// {
+    ///     "apples": 3,
+    ///     "pears": 5,
+    /// }
+    /// ```
+    #[cfg(feature = "ron")]
+    pub fn show_ron<T: serde::Serialize>(&self, value: &T) -> &Self {
+        if self.speaking() {
+            match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+                Ok(ron) => self.emit(&ron),
+                Err(err) => self.emit(&format!("failed to serialize as RON - {}", err)),
+            }
+        }
+        self
+    }
+
+    /// Call the provided function if this behold instance is configured to speak up
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// behold().call(&|| { println!("Hello world!"); } );
+    /// ```
+    /// Will output:
+    /// ```ignore
+    /// "Hello world!"
+    /// ```
+    /// Whereas the following:
+    ///```
+    /// use behold::behold;
+    /// behold().when(false).call(&|| { println!("Hello world!"); } );
+    /// ```
+    /// Will output nothing.
+    pub fn call(&self, f: &dyn Fn()) -> &Self {
+        if self.speaking() {
+            f()
+        }
+        self
+    }
+
+    /// Run the function with the process's stdout and stderr captured, then show everything it
+    /// wrote as one message if this behold instance is configured to speak up, so that noisy
+    /// dependencies stay quiet unless their output is wanted. Returns the function's result.
+    ///
+    /// Capturing redirects the file descriptors of the whole process, so output from other
+    /// threads is captured too, and captures on different threads wait for one another. If the
+    /// output can't be redirected the function runs with its output written as usual, and if
+    /// it panics the output is written to stderr.
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let answer = behold().tag("third-party").capture_output(|| {
+    ///     println!("Reticulating splines");
+    ///     42
+    /// });
+    /// assert_eq!(answer, 42);
+    /// behold().when(false).capture_output(|| println!("Hello world!"));
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "Reticulating splines, third-party"
+    /// ```
+    #[cfg(all(feature = "capture-output", unix))]
+    pub fn capture_output<F: FnOnce() -> R, R>(&self, f: F) -> R {
+        let (result, output) = capture::capture(f);
+        let output = output.trim_end_matches('\n');
+        if !output.is_empty() {
+            self.show(output.to_string());
+        }
+        result
+    }
+
+    /// Produce a future which awaits the future built by the provided function, but only if
+    /// this behold instance is configured to speak up; otherwise the function is never called
+    /// and the returned future completes immediately.
+    /// # Examples
+    /// ```ignore
+    /// behold().call_async(|| async { println!("{:?}", fetch_extra_state().await); }).await;
+    /// ```
+    /// Or, polling by hand:
+    /// ```
+    /// use std::future::{self, Future};
+    /// use std::task::{Context, Waker};
+    /// use behold::behold;
+    /// let mut cx = Context::from_waker(Waker::noop());
+    /// let mut call = Box::pin(behold().when(false).call_async(|| {
+    ///     println!("Hello world!");
+    ///     future::ready(())
+    /// }));
+    /// assert!(call.as_mut().poll(&mut cx).is_ready());
+    /// ```
+    /// Will output nothing.
+    #[cfg(feature = "async")]
+    pub fn call_async<F, Fut>(&self, f: F) -> CallAsync<Fut>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        if self.speaking() {
+            CallAsync::new(Some(f()))
+        } else {
+            CallAsync::new(None)
+        }
+    }
+
+    /// Determine if this instance shares the global context, rather than one from `with_context`
+    #[cfg(all(feature = "shared-memory", unix))]
+    fn uses_global_context(&self) -> bool {
+        Arc::ptr_eq(&self.context, &BEHOLD.context)
+    }
+
+    /// Determine if this instance should produce output right now, counting the message for
+    /// the exit summary if enabled
+    fn speaking(&self) -> bool {
+        let speaking = self.is_speaking();
+        let outcome = if speaking {
+            summary::Outcome::Shown
+        } else {
+            summary::Outcome::Suppressed
+        };
+        summary::record(self.tag.as_deref(), self.location.as_ref(), outcome);
+        if let (true, Some(site)) = (speaking, self.site) {
+            site.fire(self.tag.as_deref());
+        }
+        speaking
+    }
+
+    /// Determine if this instance should produce output right now, checking the cheapest
+    /// conditions first so that silent instances never take a lock
+    fn is_speaking(&self) -> bool {
+        self.speak_up
+            && ENABLED.load(Ordering::Relaxed)
+            && !self.site.is_some_and(Site::is_muted)
+            && self.passes_tag_filter()
+    }
+
+    /// Determine if this instance's tag is allowed by the global tag filter, if any
+    fn passes_tag_filter(&self) -> bool {
+        if !TAG_FILTER_SET.load(Ordering::Relaxed) {
+            return true;
+        }
+        match TAG_FILTER.read() {
+            Ok(filter) => match *filter {
+                Some(ref filter) => filter.allows(self.tag.as_deref()),
+                None => true,
+            },
+            Err(_) => true,
+        }
+    }
+
+    /// Write the message out, subject to this instance's rate limit
+    fn emit(&self, msg: &str) {
+        if self.admit() {
+            self.write(msg);
+        }
+    }
+
+    /// Determine if this instance's rate limit lets another message through, first reporting
+    /// any messages dropped since the last one was let through
+    fn admit(&self) -> bool {
+        if let Some(limit) = self.rate_limit {
+            match limit::admit(self.tag.as_deref().unwrap_or_default(), limit) {
+                Admission::Allowed => {}
+                Admission::AllowedAfterDropping(dropped) => {
+                    self.write(&format!("dropped {} messages", dropped));
+                }
+                Admission::Dropped => {
+                    summary::record(
+                        self.tag.as_deref(),
+                        self.location.as_ref(),
+                        summary::Outcome::Throttled,
+                    );
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Render the message in the current format and write it out, bypassing any rate limit -
+    /// or hold it until the innermost `buffered_scope` on this thread ends, if there is one
+    fn write(&self, msg: &str) {
+        let time = clock::system_time();
+        if !scope::hold(self, msg, time) {
+            self.write_at(msg, time);
+        }
+    }
+
+    /// Render the message, shown at the specified time, in the current format and write it out
+    fn write_at(&self, msg: &str, time: SystemTime) {
+        if let Some(banner) = banner::take() {
+            let context: Vec<String> = Behold::new()
+                .context_snapshot()
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            let context = if context.is_empty() {
+                "none".to_string()
+            } else {
+                context.join(",")
+            };
+            Behold::new().write_at(&format!("{} context: {}", banner, context), time);
+        }
+        let mut fields = Cow::Borrowed(&self.fields[..]);
+        if let Some(ref id) = self.correlation_id {
+            fields
+                .to_mut()
+                .push((CORRELATION_ID_FIELD.to_string(), id.clone()));
+        }
+        let processed = if middleware::is_active() {
+            let record = Record {
+                msg: msg.to_string(),
+                tag: self.tag.clone(),
+                level: self.level,
+                fields: fields.to_vec(),
+                location: self.location,
+                time,
+                thread: record::thread_name(),
+            };
+            match middleware::apply(record) {
+                Some(record) => Some(record),
+                None => return,
+            }
+        } else {
+            None
+        };
+        let (msg, tag, level, fields, location, time) = match processed {
+            Some(ref record) => (
+                &record.msg[..],
+                record.tag.as_deref(),
+                record.level,
+                Cow::Borrowed(&record.fields[..]),
+                record.location.as_ref(),
+                record.time,
+            ),
+            None => (
+                msg,
+                self.tag.as_deref(),
+                self.level,
+                fields,
+                self.location.as_ref(),
+                time,
+            ),
+        };
+        if dedup::is_duplicate(msg, tag, level, &fields) {
+            return;
+        }
+        let redactions = redact::REDACTIONS.read();
+        let (msg, fields) = match redactions {
+            Ok(ref redactions) if !redactions.is_empty() => {
+                let fields = fields
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = if redactions.is_sensitive(key) {
+                            redact::MASK.to_string()
+                        } else {
+                            redactions.redact(value).into_owned()
+                        };
+                        (key.clone(), value)
+                    })
+                    .collect();
+                (
+                    Cow::Owned(redactions.redact(msg).into_owned()),
+                    Cow::Owned(fields),
+                )
+            }
+            _ => (Cow::Borrowed(msg), fields),
+        };
+        // Truncate only once redacted, so that a secret cut short still matches its pattern
+        let max_length = self
+            .max_length
+            .unwrap_or_else(|| MAX_LENGTH.load(Ordering::Relaxed));
+        let msg = match format::truncate(&msg, max_length) {
+            Cow::Borrowed(_) => msg,
+            Cow::Owned(truncated) => Cow::Owned(truncated),
+        };
+        let style = STYLE.read().map(|style| style.clone()).unwrap_or_default();
+        let breadcrumb = if style.breadcrumbs {
+            scope::breadcrumb()
+        } else {
+            None
+        };
+        let thread_name = match processed {
+            Some(ref record) if style.thread_tags => Some(Cow::Borrowed(&record.thread[..])),
+            None if style.thread_tags => Some(Cow::Owned(record::thread_name())),
+            _ => None,
+        };
+        let record = format::Record {
+            msg: &msg,
+            tag,
+            prefix: self.prefix.as_deref(),
+            breadcrumb: breadcrumb.as_deref(),
+            fields: &fields,
+            location,
+            time,
+            sequence: if style.sequence_numbers {
+                Some(SEQUENCE.fetch_add(1, Ordering::Relaxed))
+            } else {
+                None
+            },
+            thread: thread_name.as_deref(),
+            level,
+        };
+        output::write(&record, &style);
+    }
+}
+
+/// Determine if bytes can be written as they are, as nothing between `write` and the output
+/// needs to see them as a message
+fn raw_bytes_allowed() -> bool {
+    #[cfg(feature = "otel")]
+    {
+        if otel::is_active() {
+            return false;
+        }
+    }
+    let redacting = redact::REDACTIONS
+        .read()
+        .map(|redactions| !redactions.is_empty())
+        .unwrap_or(true);
+    !redacting
+        && !scope::is_holding()
+        && !middleware::is_active()
+        && !sink::is_active()
+        && !dedup::is_active()
+        && !session::is_recording()
+}
+
+/// Convenience function for quickly constructing a behold instance.
+///
+/// # Examples
+///
+/// ```rust
+/// use behold::behold;
+/// behold().show("Hello world!".to_string());
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "Hello world!"
+/// ```
+pub fn behold() -> Behold {
+    Behold::new()
+}
+
+/// Make sure no output is lost before the process exits: report messages dropped by rate
+/// limits since the last one let through, write the exit summary and any trace being recorded,
+/// save persisted context and `once` counts, wait for `write_in_background` to write everything
+/// queued, then flush stdout and close the output file, folded stack file and session
+/// recording. Output shown afterwards is written straight to stdout.
+///
+/// # Examples
+///
+/// ```rust
+/// use behold::{behold, Behold};
+/// Behold::write_in_background(1024).unwrap();
+/// behold().show("Hello world!".to_string());
+/// behold::shutdown();
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "Hello world!"
+/// ```
+pub fn shutdown() {
+    limit::report_dropped();
+    summary::write();
+    config::flush();
+    // There's nowhere left to report a failure to write debugging output
+    let _ = trace::finish();
+    background::drain();
+    output::close();
+    folded::close();
+    session::close();
+}
+
+/// Record every message shown from now on to a new file at the specified path, one JSON
+/// object per line with the message, tag, timestamp in microseconds since the unix epoch,
+/// thread, location and fields, so that a debugging session can be archived and inspected
+/// later. Recording continues until `shutdown`.
+///
+/// # Examples
+///
+/// ```rust
+/// use behold::behold;
+/// let path = std::env::temp_dir().join("behold-session.jsonl");
+/// behold::record_session(&path).unwrap();
+/// behold().tag("net").field("peer", 7).show("Connected".to_string());
+/// behold::shutdown();
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+/// Will write to the file:
+/// ```ignore
+/// {"ts":1520164800000000,"msg":"Connected","tag":"net","thread":"main","fields":{"peer":"7"}}
+/// ```
+pub fn record_session<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    session::start(path.as_ref())
+}
+
+/// Read back a session recorded with `record_session`, yielding each record in the order shown,
+/// optionally only those with a tag or shown within a time range. Lines which can't be parsed
+/// are yielded as errors.
+///
+/// # Examples
+///
+/// ```rust
+/// use behold::behold;
+/// let path = std::env::temp_dir().join("behold-replay.jsonl");
+/// behold::record_session(&path).unwrap();
+/// behold().tag("net").show("Connected".to_string());
+/// behold().tag("db").show("Queried".to_string());
+/// behold::shutdown();
+/// let messages: Vec<String> = behold::replay(&path)
+///     .unwrap()
+///     .tag("net")
+///     .map(|record| record.unwrap().msg)
+///     .collect();
+/// assert_eq!(messages, vec!["Connected".to_string()]);
+/// // Show the whole session again
+/// behold::replay(&path).unwrap().print().unwrap();
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn replay<P: AsRef<Path>>(path: P) -> io::Result<Replay> {
+    Replay::open(path.as_ref())
+}
+
+/// Read commands from stdin on a background thread, so that debugging of a running program can
+/// be steered from its terminal. Type `help` for the list of commands:
+///
+/// - `set <key> <true|false>` sets a context key
+/// - `level <key> <n>` sets a context level
+/// - `dump` shows the context, levels and timing report
+/// - `mute` and `unmute` silence and restore all output
+///
+/// Only use this in programs which don't read stdin themselves.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use behold::behold;
+/// behold::stdin_control().unwrap();
+/// loop {
+///     behold().when_context("net").show("Polling".to_string());
+///     std::thread::sleep(std::time::Duration::from_secs(1));
+/// }
+/// ```
+/// Will produce the output, once `set net true` is typed:
+/// ```ignore
+/// net = true
+/// Polling
+/// Polling
+/// ```
+pub fn stdin_control() -> io::Result<()> {
+    control::start()
+}
+
+/// Every `behold!` call site reached so far, with the tag of the first message it showed and
+/// how many messages it has shown, in order of file and line - so that a codebase's
+/// diagnostics can be listed, and dead or noisy ones found
+/// # Examples
+/// ```
+/// #[macro_use]
+/// extern crate behold;
+///
+/// fn main() {
+///     for i in 0..3 {
+///         behold!().tag("loop").show(i.to_string());
+///     }
+///     behold!().when(false).show("Never shown".to_string());
+///     for call_site in behold::call_sites() {
+///         println!(
+///             "{}:{} {:?} fired {}",
+///             call_site.location.file, call_site.location.line, call_site.tag, call_site.fired
+///         );
+///     }
+/// }
+/// ```
+/// Will produce the output, after the messages themselves:
+/// ```ignore
+/// src/main.rs:6 Some("loop") fired 3
+/// src/main.rs:8 None fired 0
+/// ```
+pub fn call_sites() -> Vec<CallSite> {
+    site::call_sites()
+}
+
+/// Silence the `behold!` call sites at a file and line such as `parser.rs:88` - matching the
+/// end of the path - or with an id from `call_sites`, returning whether any site reached so far
+/// matched. Sites at a muted file and line are silenced as they're reached too, so a noisy
+/// statement can be muted before it first speaks up.
+/// # Examples
+/// ```
+/// #[macro_use]
+/// extern crate behold;
+///
+/// fn main() {
+///     behold::mute_site(&format!("{}:{}", file!(), line!() + 2));
+///     for i in 0..1000 {
+///         behold!().show(i.to_string());
+///     }
+///     behold!().show("Done".to_string());
+/// }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "src/main.rs:9 Done"
+/// ```
+/// Muting a site that hasn't been reached yet matches nothing so far, but still silences it:
+/// ```
+/// #[macro_use]
+/// extern crate behold;
+///
+/// fn main() {
+///     let site = format!("{}:{}", file!(), line!() + 4);
+///     assert!(!behold::mute_site(&site));
+///     let output = behold::test::isolated(|| {
+///         for i in 0..3 {
+///             behold!().show(i.to_string());
+///         }
+///         behold::test::output()
+///     });
+///     assert!(output.is_empty());
+///     assert!(behold::mute_site(&site));
+/// }
+/// ```
+pub fn mute_site(site: &str) -> bool {
+    site::set_muted(site, true)
+}
+
+/// Undo `mute_site` for the call sites at a file and line, or with an id, returning whether any
+/// site reached so far matched
+pub fn unmute_site(site: &str) -> bool {
+    site::set_muted(site, false)
+}
+
+/// Convenience macro for quickly constructing a behold instance that knows where it was created.
+///
+/// In terminals which support them, the location is a hyperlink to the source file; set
+/// `BEHOLD_HYPERLINKS` to `1` or `0` to override the detection.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate behold;
+///
+/// fn main() {
+///     behold!().tag("apples").show("Hello world!".to_string());
+/// }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "src/main.rs:5 Hello world!, apples"
+/// ```
+#[macro_export]
+macro_rules! behold {
+    () => {{
+        static SITE: $crate::Site = $crate::Site::new(
+            file!(),
+            line!(),
+            module_path!(),
+            option_env!("CARGO_MANIFEST_DIR"),
+        );
+        $crate::behold().site(&SITE)
+    }};
+}
+
+/// Declare the tags whose `behold_tag!` statements are compiled in, at the root of the crate
+/// using them - see `StaticTags` for overriding them at build time. Statements with any other
+/// tag compile to nothing, giving builds which carry only the diagnostics of the subsystems
+/// being worked on. Tie the declaration to the crate's own features with `cfg` attributes.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate behold;
+///
+/// static_tags!["net", "db"];
+///
+/// fn main() {
+///     let peer = 7;
+///     behold_tag!("net", |b| b.show(format!("Connected to {}", peer)));
+///     // Compiled out, along with the formatting of its message
+///     behold_tag!("cache", |b| b.show(format!("Cached {}", peer)));
+/// }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// src/main.rs:8 Connected to 7, net
+/// ```
+#[macro_export]
+macro_rules! static_tags {
+    ($($tag:expr),* $(,)?) => {
+        /// Tags whose `behold_tag!` statements are compiled in
+        #[allow(dead_code)]
+        const BEHOLD_STATIC_TAGS: $crate::StaticTags =
+            $crate::StaticTags::new(&[$($tag),*], option_env!("BEHOLD_STATIC_TAGS"));
+    };
+}
+
+/// Run the body with a `behold!()` instance carrying the tag, if the tag was declared with
+/// `static_tags!` - and compile to nothing otherwise. The tag must be a string literal.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate behold;
+///
+/// static_tags!["db"];
+///
+/// fn main() {
+///     behold_tag!("db", |b| b.when_context("slow-queries").show("SELECT 1".to_string()));
+/// }
+/// ```
+// `crate` is the calling crate, where `static_tags!` declares its tags
+#[allow(clippy::crate_in_macro_def)]
+#[macro_export]
+macro_rules! behold_tag {
+    ($tag:expr, |$behold:ident| $body:expr) => {{
+        const ALLOWED: bool = crate::BEHOLD_STATIC_TAGS.allows($tag);
+        if ALLOWED {
+            let $behold = $crate::behold!().tag($tag);
+            let _ = $body;
+        }
+    }};
+}
+
+/// Start a timer labelled with the name of the enclosing function, as for `Behold::time`, so
+/// that timing a function takes a single line without naming it twice. Pass a Behold instance
+/// to time with it rather than with `behold!()`.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate behold;
+/// use behold::behold;
+///
+/// fn load_config() {
+///     let _timer = behold_scope!();
+///     // ... load the config ...
+/// }
+///
+/// fn connect() {
+///     let _timer = behold_scope!(behold().tag("net"));
+///     // ... connect ...
+/// }
+///
+/// fn main() {
+///     load_config();
+///     connect();
+/// }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// src/main.rs:6 load_config took 1.234567ms
+/// connect took 10.5µs, net
+/// ```
+#[macro_export]
+macro_rules! behold_scope {
+    () => {
+        $crate::behold_scope!($crate::behold!())
+    };
+    ($behold:expr) => {{
+        fn f() {}
+        fn type_name_of<T>(_: T) -> &'static str {
+            ::std::any::type_name::<T>()
+        }
+        $behold.time($crate::function_name(type_name_of(f)))
+    }};
+}
+
+/// Convenience macro for a `behold!()` instance which speaks up only if the named cargo feature
+/// of the crate calling it is enabled, so that behold statements can follow the application's
+/// own feature flags. Pass an instance before the feature to gate it rather than `behold!()`.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate behold;
+/// use behold::behold;
+///
+/// fn main() {
+///     behold_when_feature!("diagnostics").show("Hello world!".to_string());
+///     behold_when_feature!(behold().tag("net"), "diagnostics").show("Connected".to_string());
+/// }
+/// ```
+/// Will produce the output, when built with `--features diagnostics`:
+/// ```ignore
+/// src/main.rs:6 Hello world!
+/// Connected, net
+/// ```
+#[macro_export]
+macro_rules! behold_when_feature {
+    ($feature:literal) => {
+        $crate::behold_when_feature!($crate::behold!(), $feature)
+    };
+    ($behold:expr, $feature:literal) => {
+        $behold.when(cfg!(feature = $feature))
+    };
+}
+
+/// The name of the function enclosing the item with the type name, as done by `behold_scope!`
+#[doc(hidden)]
+pub fn function_name(item: &'static str) -> &'static str {
+    let mut path = item.strip_suffix("::f").unwrap_or(item);
+    while let Some(enclosing) = path.strip_suffix("::{{closure}}") {
+        path = enclosing;
+    }
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// Show a banner describing the build ahead of the first message shown from now on, as for
+/// `Behold::show_banner`, with the name and version of the calling crate and the commit in its
+/// `BEHOLD_GIT_COMMIT` environment variable at build time, if set - such as by a build script
+/// printing `cargo:rustc-env=BEHOLD_GIT_COMMIT=...`.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate behold;
+/// use behold::behold;
+///
+/// fn main() {
+///     behold_banner!();
+///     behold().show("Hello world!".to_string());
+/// }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// myapp 1.2.0 (3f9c2e1) context: none
+/// "Hello world!"
+/// ```
+#[macro_export]
+macro_rules! behold_banner {
+    () => {
+        $crate::Behold::show_banner(
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            option_env!("BEHOLD_GIT_COMMIT"),
+        )
+    };
+}
+
+/// Watch expressions for changes, printing each expression and its `Debug` representation
+/// whenever it differs from the last time this line ran.
+///
+/// # Examples
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate behold;
+///
+/// fn main() {
+///     let mut total = 0;
+///     for i in 0..4 {
+///         total += i % 2;
+///         behold_watch!(total);
+///     }
+/// }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "src/main.rs:8 total = 0"
+/// "src/main.rs:8 total = 1"
+/// "src/main.rs:8 total = 2"
+/// ```
+/// Watch sites are `behold!` call sites, so they're listed by `call_sites` and can be muted:
+/// ```rust
+/// #[macro_use]
+/// extern crate behold;
+///
+/// fn main() {
+///     let output = behold::test::isolated(|| {
+///         for i in 0..3 {
+///             behold_watch!(i);
+///             behold::mute_site(&format!("{}:{}", file!(), line!() - 1));
+///         }
+///         behold::test::output()
+///     });
+///     assert_eq!(output.len(), 1);
+///     assert!(behold::call_sites().iter().any(|site| site.fired == 1));
+/// }
+/// ```
+#[macro_export]
+macro_rules! behold_watch {
+    ($($value:expr),+ $(,)?) => {
+        {
+            let behold = $crate::behold!();
+            $(
+                behold.watch(stringify!($value), &$value);
+            )+
+        }
+    };
+}
+
+/// Assert that a condition holds, but only check it while the named context key is enabled -
+/// for heavyweight invariants which are too slow to check all the time. On failure, panics
+/// with the expression, the location and a dump of the context.
+///
+/// # Examples
+///
+/// ```rust,should_panic
+/// #[macro_use]
+/// extern crate behold;
+///
+/// use behold::behold;
+///
+/// fn main() {
+///     let sorted = vec![1, 3, 2];
+///     // Not checked, since the context key isn't enabled
+///     behold_assert!(sorted.windows(2).all(|w| w[0] <= w[1]), "check-sorted");
+///     behold().set_context("check-sorted", true);
+///     behold_assert!(sorted.windows(2).all(|w| w[0] <= w[1]), "check-sorted");
+/// }
+/// ```
+/// Will panic with the message:
+/// ```ignore
+/// behold assertion failed: `sorted.windows(2).all(|w| w[0] <= w[1])` at src/main.rs:11
+/// context: check-sorted=true
+/// ```
+#[macro_export]
+macro_rules! behold_assert {
+    ($cond:expr, $key:expr $(,)?) => {
+        $crate::behold_assert!($cond, $key, "behold assertion failed: `{}`", stringify!($cond))
+    };
+    ($cond:expr, $key:expr, $($arg:tt)+) => {
+        {
+            let behold = $crate::behold();
+            if behold.resolve_context($key).map_or(false, |(value, _)| value) && !$cond {
+                panic!(
+                    "{} at {}:{}\ncontext: {}",
+                    format_args!($($arg)+),
+                    file!(),
+                    line!(),
+                    behold
+                        .context_snapshot()
+                        .iter()
+                        .map(|(key, value)| format!("{}={}", key, value))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                );
+            }
+        }
+    };
+}
+
+/// Name of the field holding the correlation ID in output
+const CORRELATION_ID_FIELD: &str = "correlation_id";
+
+thread_local! {
+    /// Buffer messages are formatted into by `show_fmt`, reused to save allocating each time
+    static FORMATTED: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Global kill switch - when false no Behold instance will speak up
+static ENABLED: AtomicBool = AtomicBool::new(true);
+/// Whether `set_tag_filter` has ever been called, so unfiltered output can skip the filter lock
+static TAG_FILTER_SET: AtomicBool = AtomicBool::new(false);
+/// Sequence number of the next message to be written, when sequence numbers are enabled
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+/// Global maximum length of messages in bytes - `usize::MAX` when messages are never truncated
+static MAX_LENGTH: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Determine if the environment asks for output to be silenced
+fn silenced_by_env() -> bool {
+    match std::env::var("BEHOLD_SILENT") {
+        Ok(value) => !(value.is_empty() || value == "0" || value.eq_ignore_ascii_case("false")),
+        Err(_) => false,
+    }
+}
+
+/// Determine if the current thread is the main thread, which the standard library names `main`
+fn is_main_thread() -> bool {
+    thread::current().name() == Some("main")
+}
+
+/// Environment variable holding a tag filter to apply at startup, as for `set_tag_filter`
+const TAG_FILTER_ENV_VAR: &str = "BEHOLD_TAGS";
+
+/// Context passed down by a parent process, if any
+fn context_from_env() -> Vec<Directive> {
+    std::env::var(CONTEXT_ENV_VAR)
+        .ok()
+        .and_then(|directives| directive::parse(&directives).ok())
+        .unwrap_or_default()
+}
+
+lazy_static! {
+    static ref BEHOLD: Behold = {
+        if silenced_by_env() {
+            ENABLED.store(false, Ordering::Relaxed);
+        }
+        if let Ok(patterns) = std::env::var(TAG_FILTER_ENV_VAR) {
+            if let Ok(mut filter) = TAG_FILTER.write() {
+                *filter = Some(TagFilter::parse(&patterns));
+                TAG_FILTER_SET.store(true, Ordering::Relaxed);
+            }
+        }
+        let directives = context_from_env();
+        let context = SharedContext::new(
+            directives
+                .iter()
+                .map(|directive| (directive.key().to_string(), directive.enabled()))
+                .collect(),
+        );
+        if let Ok(mut levels) = context.lock_levels() {
+            for directive in &directives {
+                if let Directive::Level(ref key, level) = *directive {
+                    levels.insert(key.clone(), level);
+                }
+            }
+        }
+        Behold {
+            context: Arc::new(context),
+            speak_up: true,
+            tag: None,
+            prefix: None,
+            fields: Vec::new(),
+            location: None,
+            site: None,
+            local_context: BTreeMap::new(),
+            rate_limit: None,
+            max_length: None,
+            correlation_id: None,
+            level: None,
+        }
+    };
+    static ref STYLE: RwLock<Style> = RwLock::new(Style::default());
+    static ref TAG_FILTER: RwLock<Option<TagFilter>> = RwLock::new(None);
+}
 
 #[cfg(test)]
 include!(concat!(env!("OUT_DIR"), "/skeptic-tests.rs"));
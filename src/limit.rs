@@ -0,0 +1,170 @@
+//! Token-bucket rate limiting of output.
+use std::collections::HashMap;
+use std::mem;
+use std::sync::{Condvar, Mutex, MutexGuard, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clock;
+use Behold;
+
+/// Maximum number of messages allowed per interval
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct RateLimit {
+    pub messages: u32,
+    pub per: Duration,
+}
+
+/// Outcome of asking a bucket for permission to show a message
+pub(crate) enum Admission {
+    /// Show the message
+    Allowed,
+    /// Show the message, after reporting that this many messages were dropped beforehand
+    AllowedAfterDropping(u64),
+    /// Drop the message
+    Dropped,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+    dropped: u64,
+    /// When the first message dropped since the last notice was dropped
+    dropped_at: Instant,
+    per: Duration,
+}
+
+lazy_static! {
+    /// Buckets keyed by tag, shared by every Behold instance with that tag
+    static ref BUCKETS: Mutex<HashMap<String, Bucket>> = Mutex::new(HashMap::new());
+    /// Wakes the reporter thread when a bucket starts dropping messages
+    static ref DROPPING: Condvar = Condvar::new();
+}
+
+static REPORTER: Once = Once::new();
+
+/// Take a token from the bucket for the specified key, if one is available
+pub(crate) fn admit(key: &str, limit: RateLimit) -> Admission {
+    let mut buckets = match BUCKETS.lock() {
+        Ok(buckets) => buckets,
+        // Rate limiting is best-effort; don't lose output because another thread panicked
+        Err(_) => return Admission::Allowed,
+    };
+    let now = clock::now();
+    let capacity = f64::from(limit.messages);
+    let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+        tokens: capacity,
+        refilled: now,
+        dropped: 0,
+        dropped_at: now,
+        per: limit.per,
+    });
+    bucket.per = limit.per;
+
+    let per = limit.per.as_secs_f64();
+    let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+    if per > 0.0 {
+        bucket.tokens = (bucket.tokens + elapsed * capacity / per).min(capacity);
+    } else {
+        bucket.tokens = capacity;
+    }
+    bucket.refilled = now;
+
+    if bucket.tokens < 1.0 {
+        if bucket.dropped == 0 {
+            bucket.dropped_at = now;
+            REPORTER.call_once(|| {
+                // Without the thread, drops are still reported by the next message let through
+                let _ = thread::Builder::new()
+                    .name("behold-rate-limit".to_string())
+                    .spawn(report_periodically);
+            });
+            DROPPING.notify_one();
+        }
+        bucket.dropped += 1;
+        return Admission::Dropped;
+    }
+    bucket.tokens -= 1.0;
+    match bucket.dropped {
+        0 => Admission::Allowed,
+        dropped => {
+            bucket.dropped = 0;
+            Admission::AllowedAfterDropping(dropped)
+        }
+    }
+}
+
+/// Report the messages each bucket has dropped once an interval has passed since the first of
+/// them, so that a tag which floods and then goes quiet still has its drops reported
+fn report_periodically() {
+    let mut buckets = match BUCKETS.lock() {
+        Ok(buckets) => buckets,
+        Err(_) => return,
+    };
+    loop {
+        let now = clock::now();
+        let mut next = None;
+        let due = take_dropped(&mut buckets, |bucket| {
+            let deadline = bucket.dropped_at + bucket.per;
+            if deadline > now {
+                next = Some(next.map_or(deadline, |next: Instant| next.min(deadline)));
+            }
+            deadline <= now
+        });
+        if !due.is_empty() {
+            drop(buckets);
+            write_notices(due);
+            buckets = match BUCKETS.lock() {
+                Ok(buckets) => buckets,
+                Err(_) => return,
+            };
+            continue;
+        }
+        let waited = match next {
+            Some(next) => DROPPING
+                .wait_timeout(buckets, next.saturating_duration_since(now))
+                .map(|(buckets, _)| buckets)
+                .ok(),
+            None => DROPPING.wait(buckets).ok(),
+        };
+        buckets = match waited {
+            Some(buckets) => buckets,
+            None => return,
+        };
+    }
+}
+
+/// Report the messages each bucket dropped since it last let one through, as no later message
+/// will report them once the program exits
+pub(crate) fn report_dropped() {
+    let dropped = match BUCKETS.lock() {
+        Ok(mut buckets) => take_dropped(&mut buckets, |_| true),
+        Err(_) => return,
+    };
+    write_notices(dropped);
+}
+
+/// Take the counts of messages dropped by the chosen buckets, sorted by key
+fn take_dropped<F: FnMut(&Bucket) -> bool>(
+    buckets: &mut MutexGuard<'_, HashMap<String, Bucket>>,
+    mut chosen: F,
+) -> Vec<(String, u64)> {
+    let mut dropped = buckets
+        .iter_mut()
+        .filter(|(_, bucket)| bucket.dropped > 0 && chosen(bucket))
+        .map(|(key, bucket)| (key.clone(), mem::take(&mut bucket.dropped)))
+        .collect::<Vec<_>>();
+    dropped.sort();
+    dropped
+}
+
+fn write_notices(dropped: Vec<(String, u64)>) {
+    for (key, dropped) in dropped {
+        let behold = if key.is_empty() {
+            Behold::new()
+        } else {
+            Behold::new().tag(&key)
+        };
+        behold.write(&format!("dropped {} messages", dropped));
+    }
+}
@@ -0,0 +1,95 @@
+//! A logger for the log crate, letting behold act as the filter for existing log statements.
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use {Behold, Level, Location};
+
+/// Shows log records whose target is enabled in context, gated the way `when_context` is: a
+/// record is shown when its target is set to true, or failing that when a module pattern such
+/// as `myapp::net::*` covers the module it came from. The usual conditionals such as tag
+/// filters apply too, with the target as the tag. Records are shown at their level.
+///
+/// Targets default to the module path, so `myapp::net` enables the log statements of that
+/// module alone while `myapp::net::*` enables those beneath it as well.
+/// # Examples
+/// ```
+/// # extern crate behold;
+/// # #[macro_use]
+/// # extern crate log;
+/// # fn main() {
+/// use behold::{behold, BeholdLogger};
+/// BeholdLogger::new().init().unwrap();
+/// behold().set_context("net", true);
+/// debug!(target: "net", "Connected");
+/// debug!(target: "db", "Queried");
+/// # }
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "DEBUG src/main.rs:6 Connected, net"
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BeholdLogger {
+    /// Most verbose level let through to the context check
+    max_level: LevelFilter,
+}
+
+impl Default for BeholdLogger {
+    fn default() -> Self {
+        BeholdLogger {
+            max_level: LevelFilter::Trace,
+        }
+    }
+}
+
+impl BeholdLogger {
+    /// Create a logger letting every level through to the context check
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return a logger which drops records more verbose than the level, whatever the context
+    pub fn with_max_level(&self, max_level: LevelFilter) -> Self {
+        BeholdLogger { max_level }
+    }
+
+    /// Install the logger as the log crate's logger, failing if one is already installed
+    pub fn init(self) -> Result<(), SetLoggerError> {
+        let max_level = self.max_level;
+        log::set_boxed_logger(Box::new(self)).map(|()| log::set_max_level(max_level))
+    }
+}
+
+impl Log for BeholdLogger {
+    /// Only the level is checked here, since module patterns need the record's module -
+    /// `log_enabled!` is true whenever the level is
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.max_level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        };
+        let mut behold = Behold::new().tag(record.target()).level(level);
+        if let (Some(file), Some(line), Some(module_path)) = (
+            record.file_static(),
+            record.line(),
+            record.module_path_static(),
+        ) {
+            behold = behold.location(Location::new(file, line, module_path));
+        }
+        let behold = behold.when_context(record.target());
+        if behold.is_speaking() {
+            behold.show(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
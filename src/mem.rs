@@ -0,0 +1,85 @@
+//! Reporting of the memory used by the current process.
+#[cfg(target_os = "linux")]
+use std::fs;
+
+/// Memory used by the current process, as far as the platform reports it
+pub(crate) struct Usage {
+    /// Resident set size in bytes
+    pub resident: Option<u64>,
+    /// Peak resident set size in bytes
+    pub peak_resident: Option<u64>,
+}
+
+/// Read the memory usage of the current process from `/proc/self/status`
+#[cfg(target_os = "linux")]
+pub(crate) fn usage() -> Usage {
+    let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+    let field = |name: &str| {
+        status
+            .lines()
+            .find(|line| line.starts_with(name))
+            .and_then(|line| {
+                line[name.len()..]
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse()
+                    .ok()
+            })
+            .map(|kilobytes: u64| kilobytes * 1024)
+    };
+    Usage {
+        resident: field("VmRSS:"),
+        peak_resident: field("VmHWM:"),
+    }
+}
+
+/// Read the peak memory usage of the current process with `getrusage` - the current resident
+/// set size isn't available without platform specific APIs
+#[cfg(all(unix, not(target_os = "linux")))]
+pub(crate) fn usage() -> Usage {
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    let peak_resident = if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut rusage) } == 0 {
+        let max_rss = rusage.ru_maxrss as u64;
+        // macOS reports bytes where other platforms report kilobytes
+        if cfg!(any(target_os = "macos", target_os = "ios")) {
+            Some(max_rss)
+        } else {
+            Some(max_rss * 1024)
+        }
+    } else {
+        None
+    };
+    Usage {
+        resident: None,
+        peak_resident,
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn usage() -> Usage {
+    Usage {
+        resident: None,
+        peak_resident: None,
+    }
+}
+
+/// Render the memory usage after a label, such as `parsed: resident 12.3 MiB, peak 15.0 MiB`
+pub(crate) fn describe(label: &str, usage: &Usage) -> String {
+    let mut parts = Vec::new();
+    if let Some(resident) = usage.resident {
+        parts.push(format!("resident {}", mebibytes(resident)));
+    }
+    if let Some(peak) = usage.peak_resident {
+        parts.push(format!("peak {}", mebibytes(peak)));
+    }
+    if parts.is_empty() {
+        format!("{}: memory usage unavailable", label)
+    } else {
+        format!("{}: {}", label, parts.join(", "))
+    }
+}
+
+fn mebibytes(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
@@ -0,0 +1,89 @@
+//! Filters and transformations applied to every message before it is rendered.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use Record;
+
+/// Inspects each message before it is rendered, returning the record to render - modified or
+/// not - or None to drop the message, for redaction, sampling, enrichment and the like.
+///
+/// Closures taking and returning records implement it too.
+/// # Examples
+/// ```
+/// use behold::{behold, Middleware, Record};
+/// struct Hostname;
+///
+/// impl Middleware for Hostname {
+///     fn process(&self, mut record: Record) -> Option<Record> {
+///         record.fields.push(("host".to_string(), "web-1".to_string()));
+///         Some(record)
+///     }
+/// }
+///
+/// behold().add_middleware(Hostname);
+/// behold().show("Hello world!".to_string());
+/// behold().clear_middleware();
+/// ```
+/// Will produce the output:
+/// ```ignore
+/// "Hello world! host=web-1"
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Return the record to render, or None to drop it
+    fn process(&self, record: Record) -> Option<Record>;
+}
+
+impl<F: Fn(Record) -> Option<Record> + Send + Sync> Middleware for F {
+    fn process(&self, record: Record) -> Option<Record> {
+        self(record)
+    }
+}
+
+lazy_static! {
+    /// Middleware added with `add_middleware`, applied in the order added
+    static ref MIDDLEWARE: RwLock<Vec<Box<dyn Middleware>>> = RwLock::new(Vec::new());
+}
+
+/// Whether any middleware has been added, so that records needn't be copied otherwise
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn add(middleware: Box<dyn Middleware>) {
+    match MIDDLEWARE.write() {
+        Ok(mut chain) => {
+            chain.push(middleware);
+            ACTIVE.store(true, Ordering::Release);
+        }
+        Err(err) => panic!(
+            "add_middleware called on an instance of Behold - lock poisoned - {:?}!",
+            err
+        ),
+    }
+}
+
+pub(crate) fn clear() {
+    match MIDDLEWARE.write() {
+        Ok(mut chain) => {
+            chain.clear();
+            ACTIVE.store(false, Ordering::Release);
+        }
+        Err(err) => panic!(
+            "clear_middleware called on an instance of Behold - lock poisoned - {:?}!",
+            err
+        ),
+    }
+}
+
+/// Determine if any middleware has been added, without locking
+pub(crate) fn is_active() -> bool {
+    ACTIVE.load(Ordering::Acquire)
+}
+
+/// Pass the record through every middleware in turn, stopping as soon as one drops it
+pub(crate) fn apply(record: Record) -> Option<Record> {
+    match MIDDLEWARE.read() {
+        Ok(chain) => chain
+            .iter()
+            .try_fold(record, |record, middleware| middleware.process(record)),
+        Err(_) => Some(record),
+    }
+}
@@ -0,0 +1,70 @@
+//! Wrappers which report when the value they hold is dropped.
+use std::fmt::{self, Debug};
+use std::ops::{Deref, DerefMut};
+
+use Behold;
+
+/// Wrapper returned by `on_drop` - derefs to the wrapped value, and shows a message when it's
+/// dropped
+pub struct OnDrop<T> {
+    value: T,
+    label: String,
+    /// Renders the value when it's dropped, if it can be rendered
+    describe: Option<fn(&T) -> String>,
+    /// The instance which wrapped the value, or None if it wasn't speaking up
+    behold: Option<Behold>,
+}
+
+impl<T> OnDrop<T> {
+    pub(crate) fn new(
+        behold: Option<Behold>,
+        value: T,
+        label: &str,
+        describe: Option<fn(&T) -> String>,
+    ) -> Self {
+        OnDrop {
+            value,
+            label: label.to_string(),
+            describe,
+            behold,
+        }
+    }
+}
+
+/// Render a value with its `Debug` implementation
+pub(crate) fn debug<T: Debug>(value: &T) -> String {
+    format!("{:?}", value)
+}
+
+impl<T> Deref for OnDrop<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for OnDrop<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Debug> Debug for OnDrop<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T> Drop for OnDrop<T> {
+    fn drop(&mut self) {
+        if let Some(behold) = self.behold.take() {
+            match self.describe {
+                Some(describe) => {
+                    behold.show(format!("{} dropped: {}", self.label, describe(&self.value)))
+                }
+                None => behold.show(format!("{} dropped", self.label)),
+            };
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Counting how many times each call site has spoken up, for `once` and `first_n`.
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use config;
+use Location;
+
+lazy_static! {
+    /// How many times each site has been allowed to speak up
+    static ref SHOWN: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+    /// File the counts are saved to whenever they change, if set with `persist_once_state`
+    static ref PERSIST_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// Identify a call site by its location if known, otherwise by its tag
+pub(crate) fn site(location: Option<&Location>, tag: Option<&str>) -> String {
+    match location {
+        Some(location) => format!("{}:{}", location.file, location.line),
+        None => tag.unwrap_or_default().to_string(),
+    }
+}
+
+/// Allow the site to speak up if it has done so fewer than `limit` times, counting this time
+pub(crate) fn admit(site: String, limit: u64) -> bool {
+    let mut shown = match SHOWN.lock() {
+        Ok(shown) => shown,
+        Err(err) => panic!(
+            "first_n called on an instance of Behold - mutex already acquired - {:?}!",
+            err
+        ),
+    };
+    let count = shown.entry(site).or_insert(0);
+    if *count >= limit {
+        return false;
+    }
+    *count += 1;
+    if let Ok(path) = PERSIST_PATH.lock() {
+        if let Some(ref path) = *path {
+            config::save_later(path, render(&shown));
+        }
+    }
+    true
+}
+
+/// Load counts saved by a previous run if there are any, then save them whenever they change
+pub(crate) fn persist(path: &Path) -> io::Result<()> {
+    let mut shown = match SHOWN.lock() {
+        Ok(shown) => shown,
+        Err(err) => panic!(
+            "persist_once_state called - mutex already acquired - {:?}!",
+            err
+        ),
+    };
+    match fs::read_to_string(path) {
+        Ok(contents) => {
+            for (site, count) in parse(&contents) {
+                let saved = shown.entry(site).or_insert(0);
+                *saved = (*saved).max(count);
+            }
+        }
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    match PERSIST_PATH.lock() {
+        Ok(mut persist_path) => *persist_path = Some(path.to_path_buf()),
+        Err(err) => panic!(
+            "persist_once_state called - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+    fs::write(path, render(&shown))
+}
+
+/// Parse lines holding a count followed by a site, ignoring anything unparseable
+fn parse(contents: &str) -> Vec<(String, u64)> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ' ');
+            let count = parts.next()?.parse().ok()?;
+            Some((parts.next()?.to_string(), count))
+        })
+        .collect()
+}
+
+/// Render the counts as lines read by `parse`
+fn render(shown: &BTreeMap<String, u64>) -> String {
+    let mut contents = String::new();
+    for (site, count) in shown {
+        contents.push_str(&format!("{} {}\n", count, site));
+    }
+    contents
+}
@@ -0,0 +1,84 @@
+//! Forwarding of output to OpenTelemetry, for environments already running a collector.
+use std::sync::RwLock;
+
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, Severity};
+
+use format::Record;
+use Level;
+
+/// Forwards a record to a logger, hiding the logger's type
+type Forward = Box<dyn Fn(&Record) + Send + Sync>;
+
+lazy_static! {
+    /// Where records are forwarded to, if anywhere
+    static ref FORWARD: RwLock<Option<Forward>> = RwLock::new(None);
+}
+
+/// Forward every record to the logger from now on
+pub(crate) fn set_logger<L>(logger: L)
+where
+    L: Logger + Send + Sync + 'static,
+{
+    let forward: Forward = Box::new(move |record| {
+        let mut log = logger.create_log_record();
+        log.set_timestamp(record.time);
+        let (severity, text) = match record.level {
+            Some(Level::Error) => (Severity::Error, "ERROR"),
+            Some(Level::Warn) => (Severity::Warn, "WARN"),
+            Some(Level::Info) => (Severity::Info, "INFO"),
+            Some(Level::Debug) | None => (Severity::Debug, "DEBUG"),
+            Some(Level::Trace) => (Severity::Trace, "TRACE"),
+        };
+        log.set_severity_number(severity);
+        log.set_severity_text(text);
+        log.set_body(AnyValue::from(record.msg.to_string()));
+        if let Some(tag) = record.tag {
+            log.add_attribute("behold.tag", tag.to_string());
+        }
+        if let Some(prefix) = record.prefix {
+            log.add_attribute("behold.prefix", prefix.to_string());
+        }
+        if let Some(breadcrumb) = record.breadcrumb {
+            log.add_attribute("behold.breadcrumb", breadcrumb.to_string());
+        }
+        if let Some(sequence) = record.sequence {
+            log.add_attribute("behold.sequence", sequence as i64);
+        }
+        if let Some(thread) = record.thread {
+            log.add_attribute("thread.name", thread.to_string());
+        }
+        if let Some(location) = record.location {
+            log.add_attribute("code.file.path", location.file);
+            log.add_attribute("code.line.number", i64::from(location.line));
+            log.add_attribute("code.namespace", location.module_path);
+        }
+        for (key, value) in record.fields {
+            log.add_attribute(key.clone(), value.clone());
+        }
+        logger.emit(log);
+    });
+    match FORWARD.write() {
+        Ok(mut current) => *current = Some(forward),
+        Err(err) => panic!(
+            "set_otel_logger called on an instance of Behold - lock poisoned - {:?}!",
+            err
+        ),
+    }
+}
+
+/// Determine if records are being forwarded
+pub(crate) fn is_active() -> bool {
+    FORWARD
+        .read()
+        .map(|forward| forward.is_some())
+        .unwrap_or_default()
+}
+
+/// Forward a record to the logger, if one is set
+pub(crate) fn forward(record: &Record) {
+    if let Ok(forward) = FORWARD.read() {
+        if let Some(ref forward) = *forward {
+            forward(record);
+        }
+    }
+}
@@ -0,0 +1,519 @@
+//! Destinations for output produced by Behold instances.
+#[cfg(not(all(feature = "android", target_os = "android")))]
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use background;
+use format::{Record, Style};
+#[cfg(feature = "otel")]
+use otel;
+use session;
+use sink;
+use test;
+
+/// Receives lines of output in place of stdout
+type Interceptor = Box<dyn Fn(&str) + Send + Sync>;
+/// Renders records in place of the format
+type Formatter = Box<dyn Fn(&::Record) -> String + Send + Sync>;
+
+/// Whether a formatter has been set, so that records needn't be copied otherwise
+static FORMATTING: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    /// File that output is redirected to, if any
+    static ref OUTPUT_FILE: Mutex<Option<File>> = Mutex::new(None);
+    /// Function that lines bound for stdout are handed to instead, if any
+    static ref INTERCEPTOR: RwLock<Option<Interceptor>> = RwLock::new(None);
+    /// Function rendering records in place of the format, if any
+    static ref FORMATTER: RwLock<Option<Formatter>> = RwLock::new(None);
+    /// Inline text handed to the interceptor since the last complete line
+    static ref INTERCEPTED: Mutex<String> = Mutex::new(String::new());
+}
+
+#[cfg(not(all(feature = "android", target_os = "android")))]
+lazy_static! {
+    /// Whether stdout is a terminal known to support OSC-8 hyperlinks
+    static ref HYPERLINKS: bool = hyperlinks_supported();
+    /// Whether stdout is a terminal levels can be painted in the theme's colors on
+    static ref COLORS: bool = colors_supported();
+}
+
+/// Redirect all output to the file at the specified path, appending to it if it already exists
+pub(crate) fn set_file(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    match OUTPUT_FILE.lock() {
+        Ok(mut output) => *output = Some(file),
+        Err(err) => panic!(
+            "set_output_file called - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+    Ok(())
+}
+
+/// Hand lines bound for stdout to the function instead, or write them to stdout again if None
+pub(crate) fn set_interceptor(interceptor: Option<Interceptor>) {
+    match INTERCEPTOR.write() {
+        Ok(mut current) => *current = interceptor,
+        Err(err) => panic!(
+            "set_output_interceptor called on an instance of Behold - lock poisoned - {:?}!",
+            err
+        ),
+    }
+}
+
+/// Render records with the function instead of the format, or with the format again if None
+pub(crate) fn set_formatter(formatter: Option<Formatter>) {
+    match FORMATTER.write() {
+        Ok(mut current) => {
+            FORMATTING.store(formatter.is_some(), Ordering::Release);
+            *current = formatter;
+        }
+        Err(err) => panic!(
+            "set_formatter called on an instance of Behold - lock poisoned - {:?}!",
+            err
+        ),
+    }
+}
+
+/// Render the record with the formatter, if one is set
+fn format(record: &::Record) -> Option<String> {
+    match FORMATTER.read() {
+        Ok(formatter) => formatter.as_ref().map(|formatter| formatter(record)),
+        Err(_) => None,
+    }
+}
+
+/// Hand output to the interceptor if one is set, returning false if it isn't
+fn intercept(pending: &Pending) -> bool {
+    let interceptor = match INTERCEPTOR.read() {
+        Ok(interceptor) => interceptor,
+        Err(_) => return false,
+    };
+    let interceptor = match *interceptor {
+        Some(ref interceptor) => interceptor,
+        None => return false,
+    };
+    match *pending {
+        Pending::Line { ref line, .. } => interceptor(line),
+        Pending::Inline(ref text) => {
+            // Interceptors take whole lines, so hold on to text until its line is ended
+            let lines: Vec<String> = match INTERCEPTED.lock() {
+                Ok(mut partial) => {
+                    partial.push_str(text);
+                    let mut lines = Vec::new();
+                    while let Some(end) = partial.find('\n') {
+                        let mut line: String = partial.drain(..=end).collect();
+                        line.pop();
+                        lines.push(line);
+                    }
+                    lines
+                }
+                Err(_) => Vec::new(),
+            };
+            for line in lines {
+                interceptor(&line);
+            }
+        }
+        Pending::Raw(_) => return false,
+    }
+    true
+}
+
+/// Determine if lines bound for stdout are being intercepted
+fn is_intercepted() -> bool {
+    INTERCEPTOR
+        .read()
+        .map(|interceptor| interceptor.is_some())
+        .unwrap_or_default()
+}
+
+/// Determine if source locations written to stdout should be hyperlinks, as they can be in
+/// most modern terminals. `BEHOLD_HYPERLINKS=1` or `BEHOLD_HYPERLINKS=0` overrides detection.
+#[cfg(not(all(feature = "android", target_os = "android")))]
+fn hyperlinks_supported() -> bool {
+    match env::var("BEHOLD_HYPERLINKS").as_deref() {
+        Ok("1") | Ok("true") => return true,
+        Ok("0") | Ok("false") => return false,
+        _ => {}
+    }
+    if !io::stdout().is_terminal() || env::var("TERM").as_deref() == Ok("dumb") {
+        return false;
+    }
+    let known_program = matches!(
+        env::var("TERM_PROGRAM").as_deref(),
+        Ok("iTerm.app") | Ok("WezTerm") | Ok("vscode") | Ok("Hyper")
+    );
+    let recent_vte = env::var("VTE_VERSION")
+        .ok()
+        .and_then(|version| version.parse::<u32>().ok())
+        .is_some_and(|version| version >= 5000);
+    known_program
+        || recent_vte
+        || env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var_os("WT_SESSION").is_some()
+}
+
+/// Determine if levels written to stdout should be painted in the theme's colors, which only
+/// terminals render - `NO_COLOR` turns them off
+#[cfg(not(all(feature = "android", target_os = "android")))]
+fn colors_supported() -> bool {
+    io::stdout().is_terminal()
+        && env::var("TERM").as_deref() != Ok("dumb")
+        && env::var_os("NO_COLOR").is_none()
+}
+
+/// Output waiting to be written to the output file or stdout
+pub(crate) enum Pending {
+    /// A complete line, and the same line with hyperlinks and colors for terminals which support
+    /// them
+    Line {
+        line: String,
+        terminal: Option<String>,
+    },
+    /// Text which doesn't end the current line
+    Inline(String),
+    /// Bytes written as they are, followed by a newline
+    Raw(Vec<u8>),
+}
+
+/// Write a record to the output file if one is set, otherwise to the platform appropriate destination
+pub(crate) fn write(record: &Record, style: &Style) {
+    let formatting = FORMATTING.load(Ordering::Acquire);
+    let owned = if formatting || sink::is_active() {
+        Some(::Record::from_rendered(record))
+    } else {
+        None
+    };
+    let formatted = match owned {
+        Some(ref owned) if formatting => format(owned),
+        _ => None,
+    };
+    let custom = formatted.is_some();
+    let line = formatted.unwrap_or_else(|| style.render(record));
+    if test::capture(&line) {
+        return;
+    }
+    #[cfg(feature = "otel")]
+    {
+        otel::forward(record);
+    }
+    session::record(record);
+    if let Some(ref owned) = owned {
+        sink::write(owned);
+    }
+    if !has_file() {
+        #[cfg(all(feature = "android", target_os = "android"))]
+        {
+            android::write(record, style);
+            return;
+        }
+        #[cfg(all(feature = "systemd-journal", target_os = "linux"))]
+        {
+            if journald::write(record) {
+                return;
+            }
+        }
+        #[cfg(all(feature = "windows", windows))]
+        {
+            windows::write(&line);
+        }
+    }
+    let terminal = if custom {
+        None
+    } else {
+        for_terminal(record, style)
+    };
+    send(Pending::Line { line, terminal });
+}
+
+/// Flush stdout and close the output file, so later output goes to stdout
+pub(crate) fn close() {
+    let _ = io::stdout().flush();
+    if let Ok(mut output) = OUTPUT_FILE.lock() {
+        if let Some(file) = output.take() {
+            let _ = file.sync_all();
+        }
+    }
+}
+
+/// Determine if output is redirected to a file
+pub(crate) fn has_file() -> bool {
+    OUTPUT_FILE
+        .lock()
+        .map(|output| output.is_some())
+        .unwrap_or_default()
+}
+
+/// Render the record with its location as a hyperlink and its level in the theme's colors, as
+/// far as stdout supports them
+#[cfg(not(all(feature = "android", target_os = "android")))]
+fn for_terminal(record: &Record, style: &Style) -> Option<String> {
+    let hyperlinks = record.location.is_some() && *HYPERLINKS;
+    let colors = record.level.is_some() && style.theme.is_some() && *COLORS;
+    if !hyperlinks && !colors {
+        return None;
+    }
+    let terminal = Style {
+        hyperlinks,
+        colors,
+        ..style.clone()
+    };
+    Some(terminal.render(record))
+}
+
+/// Logcat doesn't render hyperlinks or colors
+#[cfg(all(feature = "android", target_os = "android"))]
+fn for_terminal(_record: &Record, _style: &Style) -> Option<String> {
+    None
+}
+
+/// Hand output to the background writer if one is running, otherwise write it straight away
+fn send(pending: Pending) {
+    if background::is_running() {
+        background::push(pending);
+    } else {
+        deliver(pending);
+    }
+}
+
+/// Write output to the output file if one is set, otherwise to stdout
+pub(crate) fn deliver(pending: Pending) {
+    if let Ok(mut output) = OUTPUT_FILE.lock() {
+        if let Some(ref mut file) = *output {
+            match pending {
+                Pending::Line { line, .. } => write_line(file, &line),
+                Pending::Inline(text) => {
+                    let _ = file.write_all(text.as_bytes());
+                }
+                Pending::Raw(bytes) => {
+                    let _ = file.write_all(&bytes).and_then(|_| file.write_all(b"\n"));
+                }
+            }
+            return;
+        }
+    }
+    if intercept(&pending) {
+        return;
+    }
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    match pending {
+        Pending::Line { line, terminal } => {
+            write_line(&mut stdout, terminal.as_ref().unwrap_or(&line))
+        }
+        Pending::Inline(text) => {
+            // Flush straight away, or the text won't appear until the line is ended
+            let _ = stdout
+                .write_all(text.as_bytes())
+                .and_then(|_| stdout.flush());
+        }
+        Pending::Raw(bytes) => {
+            let _ = stdout
+                .write_all(&bytes)
+                .and_then(|_| stdout.write_all(b"\n"));
+        }
+    }
+}
+
+/// Write a line and its newline with a single call, so that it can't be interleaved with lines
+/// written at the same time by other threads or by other processes appending to the same file
+fn write_line<W: Write>(out: &mut W, line: &str) {
+    let mut buffer = String::with_capacity(line.len() + 1);
+    buffer.push_str(line);
+    buffer.push('\n');
+    // There's nowhere left to report a failure to write debugging output
+    let _ = out.write_all(buffer.as_bytes());
+}
+
+/// Write text without ending the line, to the output file if one is set, otherwise to stdout
+pub(crate) fn write_inline(text: &str) {
+    if test::capture_inline(text) {
+        return;
+    }
+    send(Pending::Inline(text.to_string()));
+}
+
+/// Write bytes as they are, followed by a newline, to the output file if one is set, otherwise to
+/// stdout if it isn't a terminal - returning false if the bytes should be shown escaped instead
+pub(crate) fn write_raw(bytes: &[u8]) -> bool {
+    if test::is_isolated() {
+        return false;
+    }
+    // Logcat and the journal only accept text
+    let text_only = cfg!(any(
+        all(feature = "android", target_os = "android"),
+        all(feature = "systemd-journal", target_os = "linux")
+    ));
+    if !has_file() && (text_only || is_intercepted() || io::stdout().is_terminal()) {
+        return false;
+    }
+    send(Pending::Raw(bytes.to_vec()));
+    true
+}
+
+/// Route output to logcat, since stdout is invisible in Android apps
+#[cfg(all(feature = "android", target_os = "android"))]
+mod android {
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_int};
+
+    use format::{Record, Style};
+    use Level;
+
+    /// Priority of messages without a level - `ANDROID_LOG_DEBUG` from `android/log.h`
+    const ANDROID_LOG_DEBUG: c_int = 3;
+    /// Logcat tag used for output from untagged Behold instances
+    const DEFAULT_TAG: &str = "behold";
+
+    #[link(name = "log")]
+    extern "C" {
+        fn __android_log_write(prio: c_int, tag: *const c_char, text: *const c_char) -> c_int;
+    }
+
+    pub(crate) fn write(record: &Record, style: &Style) {
+        // The tag becomes the logcat tag, so don't repeat it in the text
+        let untagged = Record {
+            tag: None,
+            ..*record
+        };
+        let tag = c_string(record.tag.unwrap_or(DEFAULT_TAG));
+        let text = c_string(&style.render(&untagged));
+        unsafe {
+            __android_log_write(priority(record.level), tag.as_ptr(), text.as_ptr());
+        }
+    }
+
+    /// The logcat priority of a level, from `android/log.h`
+    fn priority(level: Option<Level>) -> c_int {
+        match level {
+            Some(Level::Error) => 6,
+            Some(Level::Warn) => 5,
+            Some(Level::Info) => 4,
+            Some(Level::Debug) | None => ANDROID_LOG_DEBUG,
+            Some(Level::Trace) => 2,
+        }
+    }
+
+    /// Build a C string, dropping any interior nul bytes rather than losing the whole message
+    fn c_string(value: &str) -> CString {
+        CString::new(value.replace('\0', "")).unwrap_or_default()
+    }
+}
+
+/// Send output to the systemd journal, keeping the tag, location and fields as structured fields
+#[cfg(all(feature = "systemd-journal", target_os = "linux"))]
+mod journald {
+    use std::os::unix::net::UnixDatagram;
+
+    use format::Record;
+    use Level;
+
+    /// Where journald listens for natively formatted entries
+    const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+    /// Syslog priority of messages without a level - `LOG_DEBUG`
+    const PRIORITY: &str = "7";
+    /// Prefix of the journal fields holding fields attached with `field`
+    const FIELD_PREFIX: &str = "BEHOLD_FIELD_";
+    /// Longest field name the journal accepts
+    const MAX_FIELD_NAME: usize = 64;
+
+    lazy_static! {
+        static ref SOCKET: Option<UnixDatagram> = UnixDatagram::unbound().ok();
+    }
+
+    /// Send a record to the journal, returning false if the journal could not be reached
+    pub(crate) fn write(record: &Record) -> bool {
+        let socket = match *SOCKET {
+            Some(ref socket) => socket,
+            None => return false,
+        };
+        let mut entry = Vec::new();
+        append_field(&mut entry, "MESSAGE", record.msg);
+        let priority = match record.level {
+            Some(Level::Error) => "3",
+            Some(Level::Warn) => "4",
+            Some(Level::Info) => "6",
+            Some(Level::Debug) | Some(Level::Trace) | None => PRIORITY,
+        };
+        append_field(&mut entry, "PRIORITY", priority);
+        if let Some(tag) = record.tag {
+            append_field(&mut entry, "BEHOLD_TAG", tag);
+        }
+        if let Some(prefix) = record.prefix {
+            append_field(&mut entry, "BEHOLD_PREFIX", prefix);
+        }
+        if let Some(breadcrumb) = record.breadcrumb {
+            append_field(&mut entry, "BEHOLD_BREADCRUMB", breadcrumb);
+        }
+        if let Some(sequence) = record.sequence {
+            append_field(&mut entry, "BEHOLD_SEQUENCE", &sequence.to_string());
+        }
+        if let Some(thread) = record.thread {
+            append_field(&mut entry, "BEHOLD_THREAD", thread);
+        }
+        if let Some(location) = record.location {
+            append_field(&mut entry, "CODE_FILE", location.file);
+            append_field(&mut entry, "CODE_LINE", &location.line.to_string());
+            append_field(&mut entry, "CODE_MODULE", location.module_path);
+        }
+        for (key, value) in record.fields {
+            append_field(&mut entry, &field_name(key), value);
+        }
+        socket.send_to(&entry, JOURNAL_SOCKET).is_ok()
+    }
+
+    /// Append a field using the journal native protocol
+    fn append_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+        entry.extend_from_slice(name.as_bytes());
+        if value.contains('\n') {
+            // Values containing newlines must be sent length-prefixed
+            entry.push(b'\n');
+            entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            entry.push(b'=');
+        }
+        entry.extend_from_slice(value.as_bytes());
+        entry.push(b'\n');
+    }
+
+    /// Map a user supplied key onto a valid journal field name - uppercase ASCII, digits and
+    /// underscores, within the journal's 64 byte limit - prefixed so that it can't collide with
+    /// the fields set above, such as `MESSAGE` or `CODE_LINE`, or with trusted `_` fields
+    fn field_name(key: &str) -> String {
+        let mut name = String::from(FIELD_PREFIX);
+        name.extend(key.chars().map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        }));
+        name.truncate(MAX_FIELD_NAME);
+        name
+    }
+}
+
+/// Mirror output to the debugger, since GUI apps frequently have no console
+#[cfg(all(feature = "windows", windows))]
+mod windows {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn OutputDebugStringW(output: *const u16);
+    }
+
+    pub(crate) fn write(line: &str) {
+        let wide: Vec<u16> = line
+            .encode_utf16()
+            .chain("\n".encode_utf16())
+            .chain(Some(0))
+            .collect();
+        unsafe {
+            OutputDebugStringW(wide.as_ptr());
+        }
+    }
+}
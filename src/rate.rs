@@ -0,0 +1,82 @@
+//! Counting of events per label, reported as rates per second.
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clock;
+use Behold;
+
+/// Events counted since the last report
+struct Window {
+    /// When the last report was made, or the first event counted
+    started: Option<Instant>,
+    counts: BTreeMap<String, u64>,
+}
+
+lazy_static! {
+    static ref WINDOW: Mutex<Window> = Mutex::new(Window {
+        started: None,
+        counts: BTreeMap::new(),
+    });
+}
+
+pub(crate) fn tick(label: &str) {
+    match WINDOW.lock() {
+        Ok(mut window) => {
+            window.started.get_or_insert_with(clock::now);
+            match window.counts.get_mut(label) {
+                Some(count) => *count += 1,
+                None => {
+                    window.counts.insert(label.to_string(), 1);
+                }
+            }
+        }
+        Err(err) => panic!(
+            "tick called on an instance of Behold - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+}
+
+/// Render the events per second of each label since the last report, starting a new window,
+/// or None if nothing was counted
+fn take() -> Option<String> {
+    let mut window = WINDOW.lock().ok()?;
+    let now = clock::now();
+    let started = window.started.replace(now)?;
+    let counts = std::mem::take(&mut window.counts);
+    if counts.is_empty() {
+        return None;
+    }
+    let elapsed = now.duration_since(started);
+    let mut report = format!("rates over {:?}:", elapsed);
+    for (label, count) in counts {
+        let rate = if elapsed.as_secs_f64() > 0.0 {
+            format!("{:.1}/s", count as f64 / elapsed.as_secs_f64())
+        } else {
+            "-/s".to_string()
+        };
+        report.push_str(&format!("\n  {}: {} ({})", label, count, rate));
+    }
+    Some(report)
+}
+
+/// Render the rates since the last report, starting a new window
+pub(crate) fn report() -> String {
+    take().unwrap_or_else(|| "rates: no events counted".to_string())
+}
+
+/// Write the rates from a background thread at the interval, skipping windows with no events
+pub(crate) fn report_every(interval: Duration) -> io::Result<()> {
+    thread::Builder::new()
+        .name("behold-rates".to_string())
+        .spawn(move || loop {
+            thread::sleep(interval);
+            if let Some(report) = take() {
+                Behold::new().write(&report);
+            }
+        })?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! The structured form of a message, as seen by middleware, formatters and sinks.
+use std::thread;
+use std::time::SystemTime;
+
+use format;
+use {Level, Location};
+
+/// A message being shown. Middleware sees it before it is truncated or redacted and may change
+/// it; formatters set with `set_formatter` and sinks added with `add_sink` see it as it is
+/// finally rendered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// The message being shown
+    pub msg: String,
+    /// Tag of the Behold instance showing the message
+    pub tag: Option<String>,
+    /// How severe the message is, if set with `level`
+    pub level: Option<Level>,
+    /// Key-value pairs attached to the message, including any correlation id
+    pub fields: Vec<(String, String)>,
+    /// Where the message was shown from, if known
+    pub location: Option<Location>,
+    /// When the message was shown
+    pub time: SystemTime,
+    /// Name of the thread the message was shown from, or its id if unnamed
+    pub thread: String,
+}
+
+impl Record {
+    /// Copy a record on its way to the output
+    pub(crate) fn from_rendered(record: &format::Record) -> Self {
+        Record {
+            msg: record.msg.to_string(),
+            tag: record.tag.map(str::to_string),
+            level: record.level,
+            fields: record.fields.to_vec(),
+            location: record.location.copied(),
+            time: record.time,
+            thread: match record.thread {
+                Some(thread) => thread.to_string(),
+                None => thread_name(),
+            },
+        }
+    }
+}
+
+/// Name of the current thread, or its id if unnamed
+pub(crate) fn thread_name() -> String {
+    let current = thread::current();
+    match current.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", current.id()),
+    }
+}
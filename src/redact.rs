@@ -0,0 +1,125 @@
+//! Masking of sensitive data before it is output.
+use std::borrow::Cow;
+use std::sync::RwLock;
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+/// Replacement for any masked value
+pub(crate) const MASK: &str = "***";
+
+/// Rules for masking sensitive data in output
+#[derive(Default)]
+pub(crate) struct Redactions {
+    /// Names of fields whose values are masked, with ASCII letters lowercased
+    fields: Vec<String>,
+    /// Patterns whose matches are masked
+    #[cfg(feature = "regex")]
+    patterns: Vec<Regex>,
+}
+
+lazy_static! {
+    pub(crate) static ref REDACTIONS: RwLock<Redactions> = RwLock::new(Redactions::default());
+}
+
+impl Redactions {
+    pub(crate) fn add_field(&mut self, name: &str) {
+        self.fields.push(name.to_ascii_lowercase());
+    }
+
+    #[cfg(feature = "regex")]
+    pub(crate) fn add_pattern(&mut self, pattern: Regex) {
+        self.patterns.push(pattern);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        #[cfg(feature = "regex")]
+        {
+            if !self.patterns.is_empty() {
+                return false;
+            }
+        }
+        self.fields.is_empty()
+    }
+
+    /// Determine if values of the named field should be masked
+    pub(crate) fn is_sensitive(&self, name: &str) -> bool {
+        self.fields
+            .iter()
+            .any(|field| field.eq_ignore_ascii_case(name))
+    }
+
+    /// Mask sensitive data within free text
+    pub(crate) fn redact<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for field in &self.fields {
+            if let Some(redacted) = redact_field(&text, field) {
+                text = Cow::Owned(redacted);
+            }
+        }
+        #[cfg(feature = "regex")]
+        {
+            for pattern in &self.patterns {
+                if pattern.is_match(&text) {
+                    text = Cow::Owned(pattern.replace_all(&text, MASK).into_owned());
+                }
+            }
+        }
+        text
+    }
+}
+
+/// Mask the values assigned to a field within free text, such as `password=hunter2`,
+/// `password: hunter2` or `"password": "hunter2"`, returning None if there was nothing to mask
+fn redact_field(text: &str, field: &str) -> Option<String> {
+    // Lowercasing only ASCII keeps byte offsets the same as the text's, whatever the script
+    let lowered = text.to_ascii_lowercase();
+    let mut redacted = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut search = 0;
+    while let Some(found) = lowered[search..].find(field) {
+        let start = search + found;
+        let mut idx = start + field.len();
+        search = idx;
+        let bytes = text.as_bytes();
+        if idx < bytes.len() && bytes[idx] == b'"' {
+            idx += 1;
+        }
+        while idx < bytes.len() && bytes[idx] == b' ' {
+            idx += 1;
+        }
+        if idx >= bytes.len() || (bytes[idx] != b'=' && bytes[idx] != b':') {
+            continue;
+        }
+        idx += 1;
+        while idx < bytes.len() && bytes[idx] == b' ' {
+            idx += 1;
+        }
+        let (value_start, value_end) = if idx < bytes.len() && bytes[idx] == b'"' {
+            // Keep the quotes so structured text stays well formed
+            match text[idx + 1..].find('"') {
+                Some(close) => (idx + 1, idx + 1 + close),
+                None => (idx + 1, bytes.len()),
+            }
+        } else {
+            let end = text[idx..]
+                .find(|c: char| c.is_whitespace() || ",;&)}]".contains(c))
+                .map(|end| idx + end)
+                .unwrap_or(bytes.len());
+            (idx, end)
+        };
+        if value_end == value_start {
+            continue;
+        }
+        redacted.push_str(&text[copied..value_start]);
+        redacted.push_str(MASK);
+        copied = value_end;
+        search = value_end;
+    }
+    if copied == 0 {
+        None
+    } else {
+        redacted.push_str(&text[copied..]);
+        Some(redacted)
+    }
+}
@@ -0,0 +1,240 @@
+//! Per-thread scopes which configure Behold instances created within them.
+use std::cell::RefCell;
+use std::mem;
+use std::thread;
+use std::time::SystemTime;
+
+use folded;
+use Behold;
+
+thread_local! {
+    /// Tags pushed by `push_tag_scope` on this thread, innermost last
+    static TAG_SCOPES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    /// Labels of the scopes entered with `scope` on this thread, innermost last
+    static SCOPES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    /// Correlation IDs pushed by `push_correlation_id` on this thread, innermost last
+    static CORRELATION_IDS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    /// Output held by each `buffered_scope` on this thread, innermost last
+    static BUFFERS: RefCell<Vec<Vec<Held>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Guard returned by `push_tag_scope` - the tag stays in effect until this is dropped
+#[must_use = "the tag scope ends as soon as the guard is dropped"]
+pub struct TagScope {
+    /// Depth of the tag stack before this scope was pushed
+    depth: usize,
+}
+
+impl TagScope {
+    pub(crate) fn push(tag: &str) -> Self {
+        TAG_SCOPES.with(|scopes| {
+            let mut scopes = scopes.borrow_mut();
+            let depth = scopes.len();
+            scopes.push(tag.to_string());
+            TagScope { depth }
+        })
+    }
+}
+
+impl Drop for TagScope {
+    fn drop(&mut self) {
+        let depth = self.depth;
+        // Truncate rather than pop so guards dropped out of order can't leave stale tags behind
+        let _ = TAG_SCOPES.try_with(|scopes| scopes.borrow_mut().truncate(depth));
+    }
+}
+
+/// The innermost tag scope active on this thread, if any
+pub(crate) fn current_tag() -> Option<String> {
+    TAG_SCOPES
+        .try_with(|scopes| scopes.borrow().last().cloned())
+        .unwrap_or_default()
+}
+
+/// The trail of tag scopes active on this thread, outermost first, such as `app > request > db`
+pub(crate) fn breadcrumb() -> Option<String> {
+    TAG_SCOPES
+        .try_with(|scopes| {
+            let scopes = scopes.borrow();
+            if scopes.is_empty() {
+                None
+            } else {
+                Some(scopes.join(" > "))
+            }
+        })
+        .unwrap_or_default()
+}
+
+/// Guard returned by `push_correlation_id` - the ID stays in effect until this is dropped
+#[must_use = "the correlation ID is forgotten as soon as the guard is dropped"]
+pub struct CorrelationScope {
+    /// Depth of the correlation ID stack before this ID was pushed
+    depth: usize,
+}
+
+impl CorrelationScope {
+    pub(crate) fn push(id: String) -> Self {
+        CORRELATION_IDS.with(|ids| {
+            let mut ids = ids.borrow_mut();
+            let depth = ids.len();
+            ids.push(id);
+            CorrelationScope { depth }
+        })
+    }
+}
+
+impl Drop for CorrelationScope {
+    fn drop(&mut self) {
+        let depth = self.depth;
+        let _ = CORRELATION_IDS.try_with(|ids| ids.borrow_mut().truncate(depth));
+    }
+}
+
+/// The innermost correlation ID pushed on this thread, if any
+pub(crate) fn current_correlation_id() -> Option<String> {
+    CORRELATION_IDS
+        .try_with(|ids| ids.borrow().last().cloned())
+        .unwrap_or_default()
+}
+
+/// Guard returned by `scope` - the nesting depth on this thread is increased until this is dropped
+#[must_use = "the scope ends as soon as the guard is dropped"]
+pub struct Scope {
+    /// Depth of the scope stack before this scope was entered
+    depth: usize,
+    /// Position of this scope's frame in the folded stack, if recording
+    frame: Option<usize>,
+}
+
+impl Scope {
+    pub(crate) fn enter(label: &str) -> Self {
+        SCOPES.with(|scopes| {
+            let mut scopes = scopes.borrow_mut();
+            let depth = scopes.len();
+            scopes.push(label.to_string());
+            Scope {
+                depth,
+                frame: folded::enter(label),
+            }
+        })
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let depth = self.depth;
+        let _ = SCOPES.try_with(|scopes| scopes.borrow_mut().truncate(depth));
+        if let Some(frame) = self.frame {
+            folded::exit(frame);
+        }
+    }
+}
+
+/// Number of scopes entered on this thread which have not yet ended
+pub(crate) fn depth() -> usize {
+    SCOPES
+        .try_with(|scopes| scopes.borrow().len())
+        .unwrap_or_default()
+}
+
+/// A message held by a buffered scope, with what's needed to write it out as it was shown
+struct Held {
+    behold: Behold,
+    msg: String,
+    time: SystemTime,
+}
+
+/// Guard returned by `buffered_scope` - output on this thread is held until this is dropped,
+/// then discarded unless flushed
+#[must_use = "output held by the scope is discarded as soon as the guard is dropped"]
+pub struct BufferedScope {
+    /// Depth of the buffer stack before this scope was entered
+    depth: usize,
+}
+
+impl BufferedScope {
+    pub(crate) fn enter() -> Self {
+        BUFFERS.with(|buffers| {
+            let mut buffers = buffers.borrow_mut();
+            let depth = buffers.len();
+            buffers.push(Vec::new());
+            BufferedScope { depth }
+        })
+    }
+
+    /// Write out the output held so far, or pass it to the enclosing buffered scope if nested
+    pub fn flush(&self) {
+        let depth = self.depth;
+        let held = BUFFERS
+            .try_with(|buffers| {
+                let mut buffers = buffers.borrow_mut();
+                let held = buffers.get_mut(depth).map(mem::take).unwrap_or_default();
+                match depth
+                    .checked_sub(1)
+                    .and_then(|outer| buffers.get_mut(outer))
+                {
+                    Some(outer) => {
+                        outer.extend(held);
+                        Vec::new()
+                    }
+                    None => held,
+                }
+            })
+            .unwrap_or_default();
+        // Written once the buffers are released, since writing can show output of its own
+        for held in held {
+            held.behold.write_at(&held.msg, held.time);
+        }
+    }
+
+    /// Flush the output held so far if the result is an error, then return the result
+    pub fn flush_on_error<T, E>(&self, result: Result<T, E>) -> Result<T, E> {
+        if result.is_err() {
+            self.flush();
+        }
+        result
+    }
+}
+
+impl Drop for BufferedScope {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.flush();
+        }
+        let depth = self.depth;
+        let _ = BUFFERS.try_with(|buffers| buffers.borrow_mut().truncate(depth));
+    }
+}
+
+/// Determine if a buffered scope on this thread is holding output
+pub(crate) fn is_holding() -> bool {
+    BUFFERS
+        .try_with(|buffers| {
+            buffers
+                .try_borrow()
+                .map(|buffers| !buffers.is_empty())
+                .unwrap_or(true)
+        })
+        .unwrap_or_default()
+}
+
+/// Hold the message in the innermost buffered scope on this thread, returning false if there
+/// isn't one
+pub(crate) fn hold(behold: &Behold, msg: &str, time: SystemTime) -> bool {
+    BUFFERS
+        .try_with(|buffers| match buffers.try_borrow_mut() {
+            Ok(mut buffers) => match buffers.last_mut() {
+                Some(buffer) => {
+                    buffer.push(Held {
+                        behold: behold.clone(),
+                        msg: msg.to_string(),
+                        time,
+                    });
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,327 @@
+//! Recording of every record shown to a file, so that a debugging session can be archived,
+//! and reading it back.
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Lines, Write};
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use format::{json_string as string, Record};
+use record::thread_name;
+use {Behold, Level};
+
+lazy_static! {
+    /// File records are appended to, if recording
+    static ref FILE: Mutex<Option<File>> = Mutex::new(None);
+}
+
+/// Whether a session is being recorded, so that records needn't be rendered otherwise
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Record every record to a new file at the specified path from now on
+pub(crate) fn start(path: &Path) -> io::Result<()> {
+    let file = File::create(path)?;
+    match FILE.lock() {
+        Ok(mut session) => *session = Some(file),
+        Err(err) => panic!(
+            "record_session called - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+    RECORDING.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stop recording and close the file
+pub(crate) fn close() {
+    RECORDING.store(false, Ordering::Relaxed);
+    if let Ok(mut session) = FILE.lock() {
+        if let Some(file) = session.take() {
+            let _ = file.sync_all();
+        }
+    }
+}
+
+/// Determine if a session is being recorded
+pub(crate) fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+/// Append the record to the session as a line of JSON, if recording
+pub(crate) fn record(record: &Record) {
+    if !RECORDING.load(Ordering::Relaxed) {
+        return;
+    }
+    let line = render(record);
+    if let Ok(mut session) = FILE.lock() {
+        if let Some(ref mut file) = *session {
+            // There's nowhere to report a failure to write debugging output
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
+
+/// Render the record as a line of JSON, leaving out whatever it doesn't have
+fn render(record: &Record) -> String {
+    let micros = record
+        .time
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_micros())
+        .unwrap_or_default();
+    let mut line = format!("{{\"ts\":{},\"msg\":{}", micros, string(record.msg));
+    if let Some(tag) = record.tag {
+        line.push_str(&format!(",\"tag\":{}", string(tag)));
+    }
+    if let Some(level) = record.level {
+        line.push_str(&format!(",\"level\":{}", string(level.as_str())));
+    }
+    let thread = match record.thread {
+        Some(thread) => thread.to_string(),
+        None => thread_name(),
+    };
+    line.push_str(&format!(",\"thread\":{}", string(&thread)));
+    if let Some(location) = record.location {
+        line.push_str(&format!(
+            ",\"file\":{},\"line\":{},\"module\":{}",
+            string(location.file),
+            location.line,
+            string(location.module_path)
+        ));
+    }
+    if !record.fields.is_empty() {
+        let fields: Vec<String> = record
+            .fields
+            .iter()
+            .map(|(key, value)| format!("{}:{}", string(key), string(value)))
+            .collect();
+        line.push_str(&format!(",\"fields\":{{{}}}", fields.join(",")));
+    }
+    line.push_str("}\n");
+    line
+}
+
+/// A record read back from a session recorded with `record_session`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionRecord {
+    /// The message shown
+    pub msg: String,
+    /// Tag of the Behold instance which showed the message, if any
+    pub tag: Option<String>,
+    /// How severe the message was, if set
+    pub level: Option<Level>,
+    /// When the message was shown, to the microsecond
+    pub time: SystemTime,
+    /// Name of the thread which showed the message, or its id if unnamed
+    pub thread: String,
+    /// File the message was shown from, if known
+    pub file: Option<String>,
+    /// Line the message was shown from, if known
+    pub line: Option<u32>,
+    /// Module the message was shown from, if known
+    pub module: Option<String>,
+    /// Key-value pairs attached to the message
+    pub fields: Vec<(String, String)>,
+}
+
+/// Iterates over the records of a session recorded with `record_session`, created by `replay`
+pub struct Replay {
+    lines: Lines<BufReader<File>>,
+    tag: Option<String>,
+    start: Option<SystemTime>,
+    end: Option<SystemTime>,
+}
+
+impl Replay {
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        Ok(Replay {
+            lines: BufReader::new(File::open(path)?).lines(),
+            tag: None,
+            start: None,
+            end: None,
+        })
+    }
+
+    /// Only yield records with the tag
+    pub fn tag(self, tag: &str) -> Self {
+        Replay {
+            tag: Some(tag.to_string()),
+            ..self
+        }
+    }
+
+    /// Only yield records shown from `start` up to but not including `end`
+    pub fn between(self, start: SystemTime, end: SystemTime) -> Self {
+        Replay {
+            start: Some(start),
+            end: Some(end),
+            ..self
+        }
+    }
+
+    /// Show every remaining record again, with its tag and fields, whatever the context
+    pub fn print(self) -> io::Result<()> {
+        for record in self {
+            let record = record?;
+            let mut behold = Behold::new();
+            if let Some(ref tag) = record.tag {
+                behold = behold.tag(tag);
+            }
+            if let Some(level) = record.level {
+                behold = behold.level(level);
+            }
+            for (key, value) in &record.fields {
+                behold = behold.field(key, value);
+            }
+            behold.write(&record.msg);
+        }
+        Ok(())
+    }
+
+    fn wanted(&self, record: &SessionRecord) -> bool {
+        (self.tag.is_none() || record.tag == self.tag)
+            && self.start.is_none_or(|start| record.time >= start)
+            && self.end.is_none_or(|end| record.time < end)
+    }
+}
+
+impl Iterator for Replay {
+    type Item = io::Result<SessionRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match parse(&line) {
+                Some(record) if self.wanted(&record) => return Some(Ok(record)),
+                Some(_) => continue,
+                None => {
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("not a recorded session line: {}", line),
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// A value within a recorded line
+enum Value {
+    String(String),
+    Number(u128),
+    Object(Vec<(String, String)>),
+}
+
+/// Parse a line written by `render`, returning None if it isn't one
+fn parse(line: &str) -> Option<SessionRecord> {
+    let mut chars = line.trim().chars().peekable();
+    let mut record = SessionRecord {
+        msg: String::new(),
+        tag: None,
+        level: None,
+        time: UNIX_EPOCH,
+        thread: String::new(),
+        file: None,
+        line: None,
+        module: None,
+        fields: Vec::new(),
+    };
+    let mut has_time = false;
+    for (key, value) in parse_object(&mut chars, &parse_value)? {
+        match (key.as_str(), value) {
+            ("ts", Value::Number(micros)) => {
+                record.time = UNIX_EPOCH + Duration::from_micros(u64::try_from(micros).ok()?);
+                has_time = true;
+            }
+            ("msg", Value::String(msg)) => record.msg = msg,
+            ("tag", Value::String(tag)) => record.tag = Some(tag),
+            ("level", Value::String(level)) => record.level = Some(Level::parse(&level)?),
+            ("thread", Value::String(thread)) => record.thread = thread,
+            ("file", Value::String(file)) => record.file = Some(file),
+            ("line", Value::Number(line)) => record.line = Some(u32::try_from(line).ok()?),
+            ("module", Value::String(module)) => record.module = Some(module),
+            ("fields", Value::Object(fields)) => record.fields = fields,
+            // Keys added by later versions are skipped
+            (_, _) => {}
+        }
+    }
+    if chars.next().is_some() || !has_time {
+        return None;
+    }
+    Some(record)
+}
+
+/// Parse an object, using the function to parse each of its values
+fn parse_object<T, F>(chars: &mut Peekable<Chars>, parse_value: &F) -> Option<Vec<(String, T)>>
+where
+    F: Fn(&mut Peekable<Chars>) -> Option<T>,
+{
+    if chars.next()? != '{' {
+        return None;
+    }
+    let mut members = Vec::new();
+    if chars.peek() == Some(&'}') {
+        chars.next();
+        return Some(members);
+    }
+    loop {
+        let key = parse_string(chars)?;
+        if chars.next()? != ':' {
+            return None;
+        }
+        members.push((key, parse_value(chars)?));
+        match chars.next()? {
+            ',' => continue,
+            '}' => return Some(members),
+            _ => return None,
+        }
+    }
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Option<Value> {
+    match *chars.peek()? {
+        '"' => parse_string(chars).map(Value::String),
+        '{' => parse_object(chars, &parse_string).map(Value::Object),
+        _ => {
+            let mut digits = String::new();
+            while let Some(digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(*digit);
+                chars.next();
+            }
+            digits.parse().ok().map(Value::Number)
+        }
+    }
+}
+
+/// Parse a string, undoing the escaping done by `string`
+fn parse_string(chars: &mut Peekable<Chars>) -> Option<String> {
+    if chars.next()? != '"' {
+        return None;
+    }
+    let mut parsed = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(parsed),
+            '\\' => match chars.next()? {
+                'n' => parsed.push('\n'),
+                'r' => parsed.push('\r'),
+                't' => parsed.push('\t'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    parsed.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => parsed.push(c),
+            },
+            c => parsed.push(c),
+        }
+    }
+}
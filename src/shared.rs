@@ -0,0 +1,143 @@
+//! Context shared between processes forked from a common parent.
+//!
+//! Keys are stored as 64-bit FNV-1a hashes in a fixed size open addressing table, held in an
+//! anonymous shared memory mapping so that it's inherited by forked children.
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, AtomicU64, AtomicU8, Ordering};
+
+/// Maximum number of distinct keys the shared table can hold
+pub const CAPACITY: usize = 1024;
+
+/// Slot value for a key which has never been set
+const UNSET: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+
+#[repr(C)]
+struct Slot {
+    /// Hash of the key in this slot, or zero if the slot is empty
+    hash: AtomicU64,
+    value: AtomicU8,
+}
+
+#[repr(C)]
+struct Table {
+    slots: [Slot; CAPACITY],
+}
+
+/// The shared table, or null if context isn't being shared
+static TABLE: AtomicPtr<Table> = AtomicPtr::new(ptr::null_mut());
+
+/// Map the shared table, if it hasn't been already
+pub(crate) fn enable() -> io::Result<()> {
+    if !TABLE.load(Ordering::Acquire).is_null() {
+        return Ok(());
+    }
+    let mapping = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            mem::size_of::<Table>(),
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    if mapping == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    // Anonymous mappings are zeroed, which is a valid empty table
+    if TABLE
+        .compare_exchange(
+            ptr::null_mut(),
+            mapping as *mut Table,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        )
+        .is_err()
+    {
+        // Another thread won the race to enable sharing
+        unsafe {
+            libc::munmap(mapping, mem::size_of::<Table>());
+        }
+    }
+    Ok(())
+}
+
+fn table() -> Option<&'static Table> {
+    let table = TABLE.load(Ordering::Acquire);
+    // The mapping is never unmapped once published
+    unsafe { table.as_ref() }
+}
+
+/// Determine if context is being shared between processes
+pub(crate) fn is_enabled() -> bool {
+    table().is_some()
+}
+
+/// Set a key in the shared table, returning false if sharing isn't enabled or the table is full
+pub(crate) fn set(key: &str, value: bool) -> bool {
+    let table = match table() {
+        Some(table) => table,
+        None => return false,
+    };
+    let hash = hash(key);
+    for slot in probe(table, hash) {
+        let existing =
+            match slot
+                .hash
+                .compare_exchange(0, hash, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => hash,
+                Err(existing) => existing,
+            };
+        if existing == hash {
+            slot.value
+                .store(if value { TRUE } else { FALSE }, Ordering::Release);
+            return true;
+        }
+    }
+    false
+}
+
+/// Look up a key in the shared table, if sharing is enabled and the key has been set
+pub(crate) fn get(key: &str) -> Option<bool> {
+    let table = table()?;
+    let hash = hash(key);
+    for slot in probe(table, hash) {
+        match slot.hash.load(Ordering::Acquire) {
+            0 => return None,
+            existing if existing == hash => {
+                return match slot.value.load(Ordering::Acquire) {
+                    UNSET => None,
+                    value => Some(value == TRUE),
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Slots to try for a hash, in order
+fn probe(table: &Table, hash: u64) -> impl Iterator<Item = &Slot> {
+    let start = (hash % CAPACITY as u64) as usize;
+    (0..CAPACITY).map(move |offset| &table.slots[(start + offset) % CAPACITY])
+}
+
+/// 64-bit FNV-1a, which is stable across processes unlike the standard library's hasher; zero
+/// is reserved for empty slots
+fn hash(key: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in key.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    if hash == 0 {
+        1
+    } else {
+        hash
+    }
+}
@@ -0,0 +1,119 @@
+//! Dumping diagnostics when the process receives a signal.
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::raw::c_int;
+use std::os::unix::io::FromRawFd;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Mutex;
+use std::{mem, ptr, thread};
+
+use libc;
+
+use {control, Behold};
+
+/// Write end of the pipe the signal handler wakes the dumping thread through, or -1
+static WAKE: AtomicI32 = AtomicI32::new(-1);
+
+lazy_static! {
+    /// Serializes setting up the pipe and dumping thread
+    static ref SETUP: Mutex<()> = Mutex::new(());
+}
+
+/// Dump diagnostics whenever the process receives the signal
+pub(crate) fn dump_on(signal: c_int) -> io::Result<()> {
+    let _setup = match SETUP.lock() {
+        Ok(setup) => setup,
+        Err(err) => panic!(
+            "dump_on_signal called - mutex already acquired - {:?}!",
+            err
+        ),
+    };
+    if WAKE.load(Ordering::Acquire) < 0 {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let reader = unsafe { File::from_raw_fd(fds[0]) };
+        let spawned = thread::Builder::new()
+            .name("behold-signal-dump".to_string())
+            .spawn(move || wait(reader));
+        if let Err(err) = spawned {
+            // The read end was closed along with the closure which owned it
+            unsafe {
+                libc::close(fds[1]);
+            }
+            return Err(err);
+        }
+        WAKE.store(fds[1], Ordering::Release);
+    }
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        action.sa_sigaction = handle as extern "C" fn(c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signal, &action, ptr::null_mut()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Wake the dumping thread - writing to a pipe is one of the few things a signal handler may do
+extern "C" fn handle(_signal: c_int) {
+    // The write may set errno, which the interrupted code could be about to read
+    let errno = errno_location();
+    let saved = if errno.is_null() {
+        0
+    } else {
+        unsafe { *errno }
+    };
+    let fd = WAKE.load(Ordering::Relaxed);
+    if fd >= 0 {
+        let byte = 0u8;
+        unsafe {
+            libc::write(fd, &byte as *const u8 as *const libc::c_void, 1);
+        }
+    }
+    if !errno.is_null() {
+        unsafe {
+            *errno = saved;
+        }
+    }
+}
+
+/// This thread's errno, or null on platforms where libc doesn't expose it
+fn errno_location() -> *mut c_int {
+    #[cfg(any(target_os = "linux", target_os = "emscripten"))]
+    {
+        unsafe { libc::__errno_location() }
+    }
+    #[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+    {
+        unsafe { libc::__errno() }
+    }
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+    {
+        unsafe { libc::__error() }
+    }
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "emscripten",
+        target_os = "android",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd"
+    )))]
+    {
+        ptr::null_mut()
+    }
+}
+
+/// Write a dump each time the signal handler wakes this thread
+fn wait(mut reader: File) {
+    let mut byte = [0u8; 1];
+    while let Ok(1) = reader.read(&mut byte) {
+        Behold::new().write(&control::dump());
+    }
+}
@@ -0,0 +1,86 @@
+//! Destinations added alongside the output, receiving every record as it is rendered.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use Record;
+
+/// Receives every message shown, after middleware, truncation and redaction, alongside the
+/// usual output - for forwarding records to a service, a channel or a test.
+///
+/// Closures taking records implement it too.
+/// # Examples
+/// ```
+/// use behold::{behold, Record, Sink};
+/// use std::sync::Mutex;
+/// struct Errors(Mutex<Vec<String>>);
+///
+/// impl Sink for Errors {
+///     fn write(&self, record: &Record) {
+///         if record.tag.as_deref() == Some("error") {
+///             self.0.lock().unwrap().push(record.msg.clone());
+///         }
+///     }
+/// }
+///
+/// behold().add_sink(Errors(Mutex::new(Vec::new())));
+/// behold().tag("error").show("Disk full".to_string());
+/// behold().clear_sinks();
+/// ```
+pub trait Sink: Send + Sync {
+    /// Receive a record, which has already been written to the usual output
+    fn write(&self, record: &Record);
+}
+
+impl<F: Fn(&Record) + Send + Sync> Sink for F {
+    fn write(&self, record: &Record) {
+        self(record)
+    }
+}
+
+lazy_static! {
+    /// Sinks added with `add_sink`, written to in the order added
+    static ref SINKS: RwLock<Vec<Box<dyn Sink>>> = RwLock::new(Vec::new());
+}
+
+/// Whether any sink has been added, so that records needn't be copied otherwise
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn add(sink: Box<dyn Sink>) {
+    match SINKS.write() {
+        Ok(mut sinks) => {
+            sinks.push(sink);
+            ACTIVE.store(true, Ordering::Release);
+        }
+        Err(err) => panic!(
+            "add_sink called on an instance of Behold - lock poisoned - {:?}!",
+            err
+        ),
+    }
+}
+
+pub(crate) fn clear() {
+    match SINKS.write() {
+        Ok(mut sinks) => {
+            sinks.clear();
+            ACTIVE.store(false, Ordering::Release);
+        }
+        Err(err) => panic!(
+            "clear_sinks called on an instance of Behold - lock poisoned - {:?}!",
+            err
+        ),
+    }
+}
+
+/// Determine if any sink has been added, without locking
+pub(crate) fn is_active() -> bool {
+    ACTIVE.load(Ordering::Acquire)
+}
+
+/// Hand the record to every sink in turn
+pub(crate) fn write(record: &Record) {
+    if let Ok(sinks) = SINKS.read() {
+        for sink in sinks.iter() {
+            sink.write(record);
+        }
+    }
+}
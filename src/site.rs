@@ -0,0 +1,187 @@
+//! A registry of every `behold!` call site reached so far, with how often each has fired,
+//! letting individual sites be muted.
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use Location;
+
+/// Where a `behold!` call site is and what it has done - a static declared by the macro at
+/// each call site, registered when first reached
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Site {
+    location: Location,
+    registered: AtomicBool,
+    /// Position of the site in the registry, once registered
+    id: AtomicUsize,
+    fired: AtomicU64,
+    /// Whether the site has been silenced with `mute_site`
+    muted: AtomicBool,
+    /// Tag of the first message shown from the site
+    tag: OnceLock<String>,
+}
+
+impl Site {
+    pub const fn new(
+        file: &'static str,
+        line: u32,
+        module_path: &'static str,
+        manifest_dir: Option<&'static str>,
+    ) -> Self {
+        Site {
+            location: Location {
+                file,
+                line,
+                module_path,
+                manifest_dir,
+            },
+            registered: AtomicBool::new(false),
+            id: AtomicUsize::new(0),
+            fired: AtomicU64::new(0),
+            muted: AtomicBool::new(false),
+            tag: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn location(&self) -> Location {
+        self.location
+    }
+
+    /// Add the site to the registry, unless already added
+    pub(crate) fn register(&'static self) {
+        if self.registered.load(Ordering::Acquire) {
+            return;
+        }
+        match REGISTRY.lock() {
+            Ok(mut registry) => {
+                if self.registered.load(Ordering::Relaxed) {
+                    return;
+                }
+                self.id.store(registry.sites.len(), Ordering::Relaxed);
+                registry.sites.push(self);
+                // Checked under the same lock as `set_muted`, so a mute can't slip in between,
+                // and before the site is marked registered, so no other thread speaks up first
+                if registry.muted.iter().any(|spec| spec.matches(self)) {
+                    self.muted.store(true, Ordering::Relaxed);
+                }
+                self.registered.store(true, Ordering::Release);
+            }
+            Err(err) => panic!("behold! called - mutex already acquired - {:?}!", err),
+        }
+    }
+
+    /// Determine if the site has been silenced with `mute_site`, without locking
+    pub(crate) fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    /// Count a message shown from the site
+    pub(crate) fn fire(&self, tag: Option<&str>) {
+        self.fired.fetch_add(1, Ordering::Relaxed);
+        if let Some(tag) = tag {
+            self.tag.get_or_init(|| tag.to_string());
+        }
+    }
+}
+
+/// A `behold!` call site reached so far, as returned by `call_sites`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallSite {
+    /// Identifies the call site to `mute_site` - the order in which it was first reached
+    pub id: usize,
+    /// Where the call site is
+    pub location: Location,
+    /// Tag of the first message shown from the call site, if any was shown with a tag
+    pub tag: Option<String>,
+    /// How many messages the call site has shown
+    pub fired: u64,
+}
+
+struct Registry {
+    /// Every site reached so far, in the order first reached
+    sites: Vec<&'static Site>,
+    /// Files and lines muted with `mute_site`, applied to sites as they're reached
+    muted: Vec<Spec>,
+}
+
+lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+        sites: Vec::new(),
+        muted: Vec::new(),
+    });
+}
+
+/// A site as identified to `mute_site`
+#[derive(PartialEq, Eq)]
+enum Spec {
+    Id(usize),
+    /// A file path, or the end of one, and a line
+    Line(String, u32),
+}
+
+impl Spec {
+    fn parse(spec: &str) -> Option<Self> {
+        if let Ok(id) = spec.parse() {
+            return Some(Spec::Id(id));
+        }
+        let (file, line) = spec.rsplit_once(':')?;
+        Some(Spec::Line(file.to_string(), line.parse().ok()?))
+    }
+
+    fn matches(&self, site: &Site) -> bool {
+        match *self {
+            Spec::Id(id) => site.id.load(Ordering::Relaxed) == id,
+            Spec::Line(ref file, line) => {
+                let path = site.location.file;
+                site.location.line == line
+                    && path.ends_with(file.as_str())
+                    && (path.len() == file.len()
+                        || path[..path.len() - file.len()].ends_with(['/', '\\']))
+            }
+        }
+    }
+}
+
+/// Silence or unsilence the sites matching the spec, now and - for a file and line - once
+/// reached, returning whether any site reached so far matched
+pub(crate) fn set_muted(spec: &str, muted: bool) -> bool {
+    let spec = match Spec::parse(spec.trim()) {
+        Some(spec) => spec,
+        None => return false,
+    };
+    let mut registry = match REGISTRY.lock() {
+        Ok(registry) => registry,
+        Err(err) => panic!("mute_site called - mutex already acquired - {:?}!", err),
+    };
+    let mut matched = false;
+    for site in registry.sites.iter().filter(|site| spec.matches(site)) {
+        site.muted.store(muted, Ordering::Relaxed);
+        matched = true;
+    }
+    if let Spec::Line(..) = spec {
+        registry.muted.retain(|existing| *existing != spec);
+        if muted {
+            registry.muted.push(spec);
+        }
+    }
+    matched
+}
+
+/// Every call site reached so far, in order of file and line
+pub(crate) fn call_sites() -> Vec<CallSite> {
+    let mut call_sites: Vec<CallSite> = match REGISTRY.lock() {
+        Ok(registry) => registry
+            .sites
+            .iter()
+            .map(|site| CallSite {
+                id: site.id.load(Ordering::Relaxed),
+                location: site.location,
+                tag: site.tag.get().cloned(),
+                fired: site.fired.load(Ordering::Relaxed),
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    call_sites.sort_by_key(|call_site| (call_site.location.file, call_site.location.line));
+    call_sites
+}
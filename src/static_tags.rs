@@ -0,0 +1,76 @@
+//! Tags compiled in by `behold_tag!`, declared with `static_tags!`.
+
+/// The tags whose `behold_tag!` statements are compiled in, declared at the crate root with
+/// `static_tags!`. A `BEHOLD_STATIC_TAGS` environment variable set at build time, such as
+/// `BEHOLD_STATIC_TAGS=net,db cargo build`, replaces the declared tags. `*` allows every tag.
+#[derive(Clone, Copy, Debug)]
+pub struct StaticTags {
+    declared: &'static [&'static str],
+    /// Comma separated tags from the build environment, if set
+    env: Option<&'static str>,
+}
+
+impl StaticTags {
+    /// Allow the declared tags, or those in the build environment if set
+    pub const fn new(declared: &'static [&'static str], env: Option<&'static str>) -> Self {
+        StaticTags { declared, env }
+    }
+
+    /// Determine at compile time if statements with the tag are compiled in
+    pub const fn allows(&self, tag: &str) -> bool {
+        if let Some(env) = self.env {
+            return list_contains(env.as_bytes(), tag.as_bytes());
+        }
+        let mut i = 0;
+        while i < self.declared.len() {
+            let declared = self.declared[i].as_bytes();
+            if equal(declared, b"*") || equal(declared, tag.as_bytes()) {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+}
+
+/// Determine if the comma separated list contains the tag or `*`, ignoring surrounding spaces
+const fn list_contains(list: &[u8], tag: &[u8]) -> bool {
+    let mut start = 0;
+    while start <= list.len() {
+        let mut end = start;
+        while end < list.len() && list[end] != b',' {
+            end += 1;
+        }
+        let (mut first, mut last) = (start, end);
+        while first < last && list[first] == b' ' {
+            first += 1;
+        }
+        while last > first && list[last - 1] == b' ' {
+            last -= 1;
+        }
+        if slice_equal(list, first, last, b"*") || slice_equal(list, first, last, tag) {
+            return true;
+        }
+        start = end + 1;
+    }
+    false
+}
+
+/// Determine if `list[first..last]` equals the other bytes, as slicing isn't allowed in const fns
+const fn slice_equal(list: &[u8], first: usize, last: usize, other: &[u8]) -> bool {
+    if last - first != other.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < other.len() {
+        if list[first + i] != other[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn equal(a: &[u8], b: &[u8]) -> bool {
+    slice_equal(a, 0, a.len(), b)
+}
@@ -0,0 +1,125 @@
+//! Values observed per label, reported as simple statistics.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+lazy_static! {
+    /// Every value observed with `observe`, keyed by label, in the order observed
+    static ref OBSERVED: Mutex<BTreeMap<String, Vec<f64>>> = Mutex::new(BTreeMap::new());
+}
+
+pub(crate) fn observe(label: &str, value: f64) {
+    match OBSERVED.lock() {
+        Ok(mut observed) => match observed.get_mut(label) {
+            Some(values) => values.push(value),
+            None => {
+                observed.insert(label.to_string(), vec![value]);
+            }
+        },
+        Err(err) => panic!(
+            "observe called on an instance of Behold - mutex already acquired - {:?}!",
+            err
+        ),
+    }
+}
+
+/// The value at or below which the percentage of the sorted values fall, by nearest rank
+fn percentile(sorted: &[f64], percent: f64) -> f64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Render the statistics of the values, which mustn't be empty
+fn describe(values: &[f64]) -> String {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let count = match values.len() {
+        1 => "1 value".to_string(),
+        n => format!("{} values", n),
+    };
+    format!(
+        "{}, min {}, max {}, mean {}, p50 {}, p90 {}, p99 {}",
+        count,
+        sorted[0],
+        sorted[sorted.len() - 1],
+        values.iter().sum::<f64>() / values.len() as f64,
+        percentile(&sorted, 50.0),
+        percentile(&sorted, 90.0),
+        percentile(&sorted, 99.0)
+    )
+}
+
+/// Number of buckets values are counted in by `histogram`
+const BUCKETS: usize = 10;
+/// Width of the bar of the fullest bucket
+const BAR_WIDTH: usize = 40;
+
+/// Render the values observed under the label as a bar chart, counting them in equal width
+/// buckets between the smallest and largest
+pub(crate) fn histogram(label: &str) -> String {
+    let observed = match OBSERVED.lock() {
+        Ok(observed) => observed,
+        Err(_) => return format!("{} histogram unavailable", label),
+    };
+    let values = match observed.get(label) {
+        Some(values) => values,
+        None => return format!("{} histogram: no values observed", label),
+    };
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let buckets = if max > min { BUCKETS } else { 1 };
+    let width = (max - min) / buckets as f64;
+    let mut counts = vec![0usize; buckets];
+    for value in values {
+        let bucket = if width > 0.0 {
+            ((value - min) / width) as usize
+        } else {
+            0
+        };
+        // The largest value belongs in the last bucket rather than one of its own
+        counts[bucket.min(buckets - 1)] += 1;
+    }
+    let ranges: Vec<String> = (0..buckets)
+        .map(|bucket| {
+            let start = min + width * bucket as f64;
+            let end = if bucket + 1 == buckets {
+                max
+            } else {
+                min + width * (bucket + 1) as f64
+            };
+            format!("{} - {}", start, end)
+        })
+        .collect();
+    let range_width = ranges.iter().map(String::len).max().unwrap_or_default();
+    let fullest = counts.iter().copied().max().unwrap_or_default().max(1);
+    let mut chart = format!("{} histogram:", label);
+    for (range, count) in ranges.iter().zip(counts) {
+        let mut bar = "#".repeat(count * BAR_WIDTH / fullest);
+        if !bar.is_empty() {
+            bar.push(' ');
+        }
+        chart.push_str(&format!(
+            "\n  {:>width$} | {}{}",
+            range,
+            bar,
+            count,
+            width = range_width
+        ));
+    }
+    chart
+}
+
+/// Render the statistics of every label observed so far, in order of label
+pub(crate) fn report() -> String {
+    let observed = match OBSERVED.lock() {
+        Ok(observed) => observed,
+        Err(_) => return "stats report unavailable".to_string(),
+    };
+    let mut report = "stats report:".to_string();
+    if observed.is_empty() {
+        report.push_str(" no values observed");
+    }
+    for (label, values) in observed.iter() {
+        report.push_str(&format!("\n  {}: {}", label, describe(values)));
+    }
+    report
+}
@@ -0,0 +1,98 @@
+//! A read-only HTTP endpoint reporting behold's state as JSON.
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+use format::json_string as string;
+use {background, output, summary, Behold, ENABLED, STYLE};
+
+/// How long a client may take to send its request or receive the response, so that one which
+/// never does can't stop others being served
+const TIMEOUT: Duration = Duration::from_secs(1);
+/// Longest request line read, beyond which the request is treated as malformed
+const MAX_REQUEST_LINE: u64 = 1024;
+
+/// Listen for requests at the address from a background thread
+pub(crate) fn serve<A: ToSocketAddrs>(address: A) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(address)?;
+    let address = listener.local_addr()?;
+    summary::count();
+    thread::Builder::new()
+        .name("behold-status".to_string())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A client going away mid-request is no concern of ours
+                let _ = respond(stream);
+            }
+        })?;
+    Ok(address)
+}
+
+fn respond(mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut request = String::new();
+    BufReader::new((&stream).take(MAX_REQUEST_LINE)).read_line(&mut request)?;
+    let mut parts = request.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/status")) => ("200 OK", status()),
+        (Some("GET"), _) => ("404 Not Found", "{\"error\":\"not found\"}".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "{\"error\":\"method not allowed\"}".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Render behold's state as a JSON object
+fn status() -> String {
+    let behold = Behold::new();
+    let tags = summary::per_tag()
+        .into_iter()
+        .map(|(tag, (shown, suppressed, throttled))| {
+            (
+                tag,
+                format!(
+                    "{{\"shown\":{},\"suppressed\":{},\"throttled\":{}}}",
+                    shown, suppressed, throttled
+                ),
+            )
+        })
+        .collect();
+    let format = STYLE
+        .read()
+        .map(|style| format!("{:?}", style.format))
+        .unwrap_or_default();
+    format!(
+        "{{\"enabled\":{},\"context\":{},\"levels\":{},\"tags\":{},\"output\":{{\"format\":{},\"file\":{},\"background\":{},\"overflow\":{},\"dropped_in_background\":{}}}}}",
+        ENABLED.load(Ordering::Relaxed),
+        object(&behold.context_snapshot()),
+        object(&behold.context.levels_snapshot()),
+        object(&tags),
+        string(&format),
+        output::has_file(),
+        background::is_running(),
+        string(&format!("{:?}", background::overflow())),
+        background::dropped()
+    )
+}
+
+/// Render a map as a JSON object, with values that are already valid JSON
+fn object<V: Display>(entries: &BTreeMap<String, V>) -> String {
+    let members: Vec<String> = entries
+        .iter()
+        .map(|(key, value)| format!("{}:{}", string(key), value))
+        .collect();
+    format!("{{{}}}", members.join(","))
+}
@@ -0,0 +1,133 @@
+//! Counting of how many messages each tag and call site showed or had suppressed, reported
+//! when the process exits so that silently disabled diagnostics don't go unnoticed.
+use std::collections::BTreeMap;
+use std::os::raw::c_int;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+
+use {Behold, Location};
+
+/// What happened to a message
+pub(crate) enum Outcome {
+    Shown,
+    /// Silenced by `when`, `when_context`, a tag filter or `disable_all`
+    Suppressed,
+    /// Dropped by a rate limit, having already been counted as shown
+    Throttled,
+}
+
+/// Where a message came from - its tag and, if it was created with `behold!`, its location
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Site {
+    tag: Option<String>,
+    location: Option<(&'static str, u32)>,
+}
+
+#[derive(Default)]
+struct Counts {
+    shown: u64,
+    suppressed: u64,
+    throttled: u64,
+}
+
+/// Whether messages are being counted, so that nothing is locked otherwise
+static COUNTING: AtomicBool = AtomicBool::new(false);
+/// Whether the report is still to be written
+static REPORTING: AtomicBool = AtomicBool::new(false);
+static REGISTER: Once = Once::new();
+
+lazy_static! {
+    static ref SITES: Mutex<BTreeMap<Site, Counts>> = Mutex::new(BTreeMap::new());
+}
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> c_int;
+}
+
+/// Start counting messages, and report the counts when the process exits
+pub(crate) fn enable() {
+    COUNTING.store(true, Ordering::Relaxed);
+    REPORTING.store(true, Ordering::Relaxed);
+    REGISTER.call_once(|| unsafe {
+        atexit(report_at_exit);
+    });
+}
+
+extern "C" fn report_at_exit() {
+    // Unwinding out of an atexit handler is undefined behaviour
+    let _ = panic::catch_unwind(write);
+}
+
+/// Write the report, unless it's already been written
+pub(crate) fn write() {
+    if REPORTING.swap(false, Ordering::Relaxed) {
+        Behold::new().write(&report());
+    }
+}
+
+/// Start counting messages without reporting the counts at exit
+#[cfg(feature = "status-endpoint")]
+pub(crate) fn count() {
+    COUNTING.store(true, Ordering::Relaxed);
+}
+
+/// Shown, suppressed and throttled counts for each tag, with untagged messages under "untagged"
+#[cfg(feature = "status-endpoint")]
+pub(crate) fn per_tag() -> BTreeMap<String, (u64, u64, u64)> {
+    let mut tags = BTreeMap::new();
+    if let Ok(sites) = SITES.lock() {
+        for (site, counts) in sites.iter() {
+            let name = site.tag.clone().unwrap_or_else(|| "untagged".to_string());
+            let totals = tags.entry(name).or_insert((0, 0, 0));
+            totals.0 += counts.shown;
+            totals.1 += counts.suppressed;
+            totals.2 += counts.throttled;
+        }
+    }
+    tags
+}
+
+/// Count what happened to a message, if counting
+pub(crate) fn record(tag: Option<&str>, location: Option<&Location>, outcome: Outcome) {
+    if !COUNTING.load(Ordering::Relaxed) {
+        return;
+    }
+    let site = Site {
+        tag: tag.map(str::to_string),
+        location: location.map(|location| (location.file, location.line)),
+    };
+    if let Ok(mut sites) = SITES.lock() {
+        let counts = sites.entry(site).or_default();
+        match outcome {
+            Outcome::Shown => counts.shown += 1,
+            Outcome::Suppressed => counts.suppressed += 1,
+            Outcome::Throttled => {
+                counts.shown = counts.shown.saturating_sub(1);
+                counts.throttled += 1;
+            }
+        }
+    }
+}
+
+fn report() -> String {
+    let sites = match SITES.lock() {
+        Ok(sites) => sites,
+        Err(_) => return "behold summary unavailable".to_string(),
+    };
+    let mut report = "behold summary:".to_string();
+    if sites.is_empty() {
+        report.push_str(" no messages");
+    }
+    for (site, counts) in sites.iter() {
+        let mut name = site.tag.clone().unwrap_or_else(|| "untagged".to_string());
+        if let Some((file, line)) = site.location {
+            name.push_str(&format!(" ({}:{})", file, line));
+        }
+        report.push_str(&format!(
+            "\n- {}: {} shown, {} suppressed, {} throttled",
+            name, counts.shown, counts.suppressed, counts.throttled
+        ));
+    }
+    report
+}
@@ -0,0 +1,305 @@
+//! Helpers for testing code which uses behold.
+//!
+//! `cargo test` runs tests on several threads of one process, so flags set by one test would
+//! otherwise leak into every other test. `isolated` gives the current thread a fresh context
+//! and captures its output for the duration of a closure, and `Mock` does the same until it's
+//! dropped, checking the output against expectations.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+
+use context::{self, SharedContext};
+
+/// A fresh context and output sink used in place of the global ones on one thread
+struct Isolation {
+    context: Arc<SharedContext>,
+    lines: Vec<String>,
+    /// Output written by `show_inline` since the last complete line
+    partial: String,
+}
+
+impl Isolation {
+    fn append(&mut self, text: &str) {
+        self.partial.push_str(text);
+        while let Some(end) = self.partial.find('\n') {
+            let mut line: String = self.partial.drain(..=end).collect();
+            line.pop();
+            self.lines.push(line);
+        }
+    }
+}
+
+thread_local! {
+    /// Isolations active on this thread, innermost last
+    static ISOLATIONS: RefCell<Vec<Isolation>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Ends an isolation when dropped, even if the closure panicked
+struct Restore {
+    thread_context: BTreeMap<String, bool>,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let _ = ISOLATIONS.try_with(|isolations| isolations.borrow_mut().pop());
+        context::restore_thread(std::mem::take(&mut self.thread_context));
+    }
+}
+
+/// Run the closure with a fresh context and output sink on the current thread, restoring
+/// everything afterwards.
+///
+/// Within the closure `behold()` instances start from an empty context, thread-local context
+/// set with `set_thread_context` starts empty, and output is captured rather than written out;
+/// read it with `output`. Global settings such as the format and tag filter are not isolated,
+/// nor are threads spawned by the closure.
+/// # Examples
+/// ```
+/// use behold::{behold, test};
+/// test::isolated(|| {
+///     behold().set_context("do-it", true);
+///     behold().when_context("do-it").show("Hello world!".to_string());
+///     assert_eq!(test::output(), vec!["Hello world!".to_string()]);
+/// });
+/// // The context set inside the closure is gone
+/// assert_eq!(behold().resolve_context("do-it"), None);
+/// ```
+pub fn isolated<F: FnOnce() -> R, R>(f: F) -> R {
+    let _restore = isolate();
+    f()
+}
+
+/// Start an isolation on the current thread, which ends when the returned guard is dropped
+fn isolate() -> Restore {
+    ISOLATIONS.with(|isolations| {
+        isolations.borrow_mut().push(Isolation {
+            context: Arc::new(SharedContext::new(BTreeMap::new())),
+            lines: Vec::new(),
+            partial: String::new(),
+        })
+    });
+    Restore {
+        thread_context: context::take_thread(),
+    }
+}
+
+/// Matches lines of output, for `Mock::expect_msg`
+pub struct Matcher {
+    description: String,
+    matches: Box<dyn Fn(&str) -> bool>,
+}
+
+impl fmt::Debug for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+/// Match lines containing the text
+pub fn contains(text: &str) -> Matcher {
+    let text = text.to_string();
+    Matcher {
+        description: format!("a line containing {:?}", text),
+        matches: Box::new(move |line| line.contains(&text)),
+    }
+}
+
+/// Match lines which are exactly the text
+pub fn equals(text: &str) -> Matcher {
+    let text = text.to_string();
+    Matcher {
+        description: format!("a line equal to {:?}", text),
+        matches: Box::new(move |line| line == text),
+    }
+}
+
+/// Match lines starting with the text
+pub fn starts_with(text: &str) -> Matcher {
+    let text = text.to_string();
+    Matcher {
+        description: format!("a line starting with {:?}", text),
+        matches: Box::new(move |line| line.starts_with(&text)),
+    }
+}
+
+/// Match lines for which the predicate returns true
+pub fn matching<F: Fn(&str) -> bool + 'static>(description: &str, predicate: F) -> Matcher {
+    Matcher {
+        description: description.to_string(),
+        matches: Box::new(predicate),
+    }
+}
+
+/// How many lines an expectation should match, for `Mock::expect_msg`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Times {
+    min: usize,
+    max: usize,
+}
+
+impl fmt::Display for Times {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.min, self.max) {
+            (min, max) if min == max => write!(f, "exactly {} times", min),
+            (min, usize::MAX) => write!(f, "at least {} times", min),
+            (min, max) => write!(f, "between {} and {} times", min, max),
+        }
+    }
+}
+
+/// Expect exactly `n` matching lines
+pub fn times(n: usize) -> Times {
+    Times { min: n, max: n }
+}
+
+/// Expect at least `n` matching lines
+pub fn at_least(n: usize) -> Times {
+    Times {
+        min: n,
+        max: usize::MAX,
+    }
+}
+
+/// Expect at most `n` matching lines
+pub fn at_most(n: usize) -> Times {
+    Times { min: 0, max: n }
+}
+
+/// Expect no matching lines
+pub fn never() -> Times {
+    times(0)
+}
+
+/// Captures output on the current thread, as `isolated` does, until it's dropped - at which
+/// point the output is checked against every expectation, panicking if any isn't met.
+///
+/// Expectations match rendered lines, including any tag or fields.
+/// # Examples
+/// ```
+/// use behold::behold;
+/// use behold::test::{contains, never, times, Mock};
+/// let mock = Mock::new();
+/// mock.expect_msg(contains("retry"), times(3))
+///     .expect_msg(contains("gave up"), never());
+/// for attempt in 1..=3 {
+///     behold().show(format!("retry {}", attempt));
+/// }
+/// ```
+pub struct Mock {
+    expectations: RefCell<Vec<(Matcher, Times)>>,
+    /// Ends the isolation once the output has been checked
+    restore: Option<Restore>,
+}
+
+impl Mock {
+    /// Start capturing output on the current thread
+    pub fn new() -> Self {
+        Mock {
+            expectations: RefCell::new(Vec::new()),
+            restore: Some(isolate()),
+        }
+    }
+
+    /// Expect lines matching the matcher to be shown the specified number of times
+    pub fn expect_msg(&self, matcher: Matcher, times: Times) -> &Self {
+        self.expectations.borrow_mut().push((matcher, times));
+        self
+    }
+
+    /// Lines of output captured so far
+    pub fn output(&self) -> Vec<String> {
+        output()
+    }
+
+    /// Describe every expectation which isn't met by the output so far
+    fn failures(&self) -> Vec<String> {
+        let lines = output();
+        self.expectations
+            .borrow()
+            .iter()
+            .filter_map(|(matcher, times)| {
+                let count = lines.iter().filter(|line| (matcher.matches)(line)).count();
+                if count < times.min || count > times.max {
+                    Some(format!(
+                        "expected {:?} {}, but it was shown {} times",
+                        matcher, times, count
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Mock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        let failures = self.failures();
+        let lines = output();
+        drop(self.restore.take());
+        // Panicking again while a test is already failing would abort it
+        if !failures.is_empty() && !thread::panicking() {
+            panic!("{}\noutput was:\n{}", failures.join("\n"), lines.join("\n"));
+        }
+    }
+}
+
+/// Lines of output captured so far by the innermost `isolated` call on this thread, or an
+/// empty list outside of `isolated`. Output from `show_inline` appears once its line is ended.
+pub fn output() -> Vec<String> {
+    ISOLATIONS
+        .try_with(|isolations| {
+            isolations
+                .borrow()
+                .last()
+                .map(|isolation| isolation.lines.clone())
+                .unwrap_or_default()
+        })
+        .unwrap_or_default()
+}
+
+/// The context to use in place of the global context on this thread, if isolated
+pub(crate) fn context() -> Option<Arc<SharedContext>> {
+    ISOLATIONS
+        .try_with(|isolations| {
+            isolations
+                .borrow()
+                .last()
+                .map(|isolation| isolation.context.clone())
+        })
+        .unwrap_or_default()
+}
+
+/// Determine if output on this thread is being captured
+pub(crate) fn is_isolated() -> bool {
+    ISOLATIONS
+        .try_with(|isolations| !isolations.borrow().is_empty())
+        .unwrap_or_default()
+}
+
+/// Capture rendered output if this thread is isolated, returning false if it isn't
+pub(crate) fn capture(rendered: &str) -> bool {
+    capture_inline(&format!("{}\n", rendered))
+}
+
+/// Capture output which doesn't end the current line if this thread is isolated, returning
+/// false if it isn't
+pub(crate) fn capture_inline(text: &str) -> bool {
+    ISOLATIONS
+        .try_with(|isolations| match isolations.borrow_mut().last_mut() {
+            Some(isolation) => {
+                isolation.append(text);
+                true
+            }
+            None => false,
+        })
+        .unwrap_or_default()
+}
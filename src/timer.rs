@@ -0,0 +1,227 @@
+//! Guards which report how long a labelled section of code took.
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use clock;
+#[cfg(feature = "cpu-time")]
+use cpu;
+use folded;
+use trace;
+use Behold;
+
+/// Guard returned by `time` - when dropped, shows how long it was alive for
+#[must_use = "the timer stops as soon as the guard is dropped"]
+pub struct Timer {
+    /// The instance which started the timer, or None if it wasn't speaking up
+    behold: Option<Behold>,
+    label: String,
+    started: Instant,
+    /// Position of this timer on this thread's stack of running timers
+    depth: usize,
+    /// Position of this timer's frame in the folded stack, if recording
+    frame: Option<usize>,
+    /// CPU time used by this thread when the timer started, if reporting CPU time
+    #[cfg(feature = "cpu-time")]
+    cpu_started: Option<Duration>,
+}
+
+/// A timer which is still running
+struct Running {
+    label: String,
+    /// Time spent in timers nested within this one
+    children: Duration,
+}
+
+/// Totals for every timer with the same label nested within the same timers
+#[derive(Default)]
+struct Totals {
+    count: u64,
+    total: Duration,
+    /// Time spent in the timer itself rather than in timers nested within it
+    own: Duration,
+}
+
+thread_local! {
+    /// Timers running on this thread, outermost first
+    static RUNNING: RefCell<Vec<Running>> = const { RefCell::new(Vec::new()) };
+}
+
+lazy_static! {
+    /// Totals keyed by the labels of the enclosing timers, outermost first, and the timer itself
+    static ref TOTALS: Mutex<BTreeMap<Vec<String>, Totals>> = Mutex::new(BTreeMap::new());
+}
+
+impl Timer {
+    pub(crate) fn start(behold: Option<Behold>, label: &str) -> Self {
+        let (depth, frame) = match behold {
+            Some(ref behold) => {
+                trace::begin(label, behold.tag.as_deref());
+                let depth = RUNNING
+                    .try_with(|running| {
+                        let mut running = running.borrow_mut();
+                        running.push(Running {
+                            label: label.to_string(),
+                            children: Duration::default(),
+                        });
+                        running.len() - 1
+                    })
+                    .unwrap_or_default();
+                (depth, folded::enter(label))
+            }
+            None => (0, None),
+        };
+        Timer {
+            behold,
+            label: label.to_string(),
+            started: clock::now(),
+            depth,
+            frame,
+            #[cfg(feature = "cpu-time")]
+            cpu_started: None,
+        }
+    }
+
+    /// Report the CPU time used by this thread alongside the wall time, to tell time spent
+    /// blocked from time spent computing
+    /// # Examples
+    /// ```
+    /// use behold::behold;
+    /// let _timer = behold().time("hash").with_cpu_time();
+    /// ```
+    /// Will produce the output:
+    /// ```ignore
+    /// "hash took 12.3ms (cpu 11.9ms)"
+    /// ```
+    #[cfg(feature = "cpu-time")]
+    pub fn with_cpu_time(mut self) -> Self {
+        if self.behold.is_some() {
+            self.cpu_started = cpu::thread_time();
+        }
+        self
+    }
+
+    /// CPU time used by this thread since the timer started, if reporting CPU time
+    fn cpu_elapsed(&self) -> Option<Duration> {
+        #[cfg(feature = "cpu-time")]
+        {
+            if let (Some(started), Some(now)) = (self.cpu_started, cpu::thread_time()) {
+                return now.checked_sub(started);
+            }
+        }
+        None
+    }
+
+    /// Add the time taken to the totals for this timer's place in the tree of timers
+    fn accumulate(&self, elapsed: Duration) {
+        let depth = self.depth;
+        let finished = RUNNING.try_with(|running| {
+            let mut running = running.borrow_mut();
+            if running.len() <= depth {
+                return None;
+            }
+            // Timers left behind by guards dropped out of order end along with this one
+            running.truncate(depth + 1);
+            let path: Vec<String> = running.iter().map(|timer| timer.label.clone()).collect();
+            let timer = running.pop()?;
+            if let Some(parent) = running.last_mut() {
+                parent.children += elapsed;
+            }
+            Some((
+                path,
+                elapsed.checked_sub(timer.children).unwrap_or_default(),
+            ))
+        });
+        if let Ok(Some((path, own))) = finished {
+            if let Ok(mut totals) = TOTALS.lock() {
+                let totals = totals.entry(path).or_default();
+                totals.count += 1;
+                totals.total += elapsed;
+                totals.own += own;
+            }
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(behold) = self.behold.take() {
+            let elapsed = clock::now().saturating_duration_since(self.started);
+            let cpu = self.cpu_elapsed();
+            trace::end(&self.label, behold.tag.as_deref());
+            if let Some(frame) = self.frame {
+                folded::exit(frame);
+            }
+            self.accumulate(elapsed);
+            match cpu {
+                Some(cpu) => {
+                    behold.show(format!("{} took {:?} (cpu {:?})", self.label, elapsed, cpu))
+                }
+                None => behold.show(format!("{} took {:?}", self.label, elapsed)),
+            };
+        }
+    }
+}
+
+/// Render the totals of every timer that has finished as an indented tree
+pub(crate) fn report() -> String {
+    let totals = match TOTALS.lock() {
+        Ok(totals) => totals,
+        Err(_) => return "timing report unavailable".to_string(),
+    };
+    let mut report = "timing report:".to_string();
+    if totals.is_empty() {
+        report.push_str(" no timers have finished");
+    }
+    for (path, totals) in totals.iter() {
+        let calls = match totals.count {
+            1 => "1 call".to_string(),
+            n => format!("{} calls", n),
+        };
+        report.push_str(&format!(
+            "\n{}{}: {}, {:?} total, {:?} self",
+            "  ".repeat(path.len()),
+            path.last().map(String::as_str).unwrap_or_default(),
+            calls,
+            totals.total,
+            totals.own
+        ));
+    }
+    report
+}
+
+/// Guard returned by `trace_call` - shows a message on entry and another, with the time taken,
+/// when dropped
+#[must_use = "the call is traced as exiting as soon as the guard is dropped"]
+pub struct TraceCall {
+    /// The instance which traced the call, or None if it wasn't speaking up
+    behold: Option<Behold>,
+    name: String,
+    started: Instant,
+}
+
+impl TraceCall {
+    pub(crate) fn enter(behold: Option<Behold>, name: &str) -> Self {
+        if let Some(ref behold) = behold {
+            behold.show(format!("-> {}", name));
+        }
+        TraceCall {
+            behold,
+            name: name.to_string(),
+            started: clock::now(),
+        }
+    }
+}
+
+impl Drop for TraceCall {
+    fn drop(&mut self) {
+        if let Some(behold) = self.behold.take() {
+            behold.show(format!(
+                "<- {} ({:?})",
+                self.name,
+                clock::now().saturating_duration_since(self.started)
+            ));
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! Recording of timers as Chrome trace events, for viewing in chrome://tracing or Perfetto.
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use clock;
+use format::json_string;
+
+/// A single begin or end event
+struct Event {
+    name: String,
+    category: Option<String>,
+    /// `B` for the beginning of a timer and `E` for its end
+    phase: char,
+    /// Microseconds since recording started
+    timestamp: u64,
+    thread: u64,
+}
+
+/// Events recorded so far, or None if timers aren't being recorded
+struct Recording {
+    started: Instant,
+    events: Vec<Event>,
+    /// Where to write the trace
+    path: PathBuf,
+}
+
+lazy_static! {
+    static ref RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+}
+
+/// Source of the small numeric thread IDs used in trace files
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD: u64 = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Guard returned by `record_trace` - timers are recorded until this is dropped or finished,
+/// at which point the trace is written out
+#[must_use = "recording stops and the trace is written as soon as the guard is dropped"]
+pub struct TraceFile {
+    /// Whether the trace has been written already
+    written: bool,
+}
+
+impl TraceFile {
+    pub(crate) fn start(path: &Path) -> Self {
+        if let Ok(mut recording) = RECORDING.lock() {
+            *recording = Some(Recording {
+                started: clock::now(),
+                events: Vec::new(),
+                path: path.to_path_buf(),
+            });
+        }
+        TraceFile { written: false }
+    }
+
+    /// Stop recording and write the trace, reporting any failure to do so
+    pub fn finish(mut self) -> io::Result<()> {
+        self.write()
+    }
+
+    fn write(&mut self) -> io::Result<()> {
+        if self.written {
+            return Ok(());
+        }
+        self.written = true;
+        finish()
+    }
+}
+
+/// Stop recording and write the trace, if recording
+pub(crate) fn finish() -> io::Result<()> {
+    let recording = match RECORDING.lock() {
+        Ok(mut recording) => recording.take(),
+        Err(_) => None,
+    };
+    match recording {
+        Some(recording) => write_events(&recording.path, &recording.events),
+        None => Ok(()),
+    }
+}
+
+impl Drop for TraceFile {
+    fn drop(&mut self) {
+        // There's nowhere left to report a failure to write debugging output
+        let _ = self.write();
+    }
+}
+
+/// Record the beginning of a timer, if recording
+pub(crate) fn begin(name: &str, category: Option<&str>) {
+    record(name, category, 'B');
+}
+
+/// Record the end of a timer, if recording
+pub(crate) fn end(name: &str, category: Option<&str>) {
+    record(name, category, 'E');
+}
+
+fn record(name: &str, category: Option<&str>, phase: char) {
+    if let Ok(mut recording) = RECORDING.lock() {
+        if let Some(ref mut recording) = *recording {
+            let timestamp = clock::now()
+                .saturating_duration_since(recording.started)
+                .as_micros() as u64;
+            recording.events.push(Event {
+                name: name.to_string(),
+                category: category.map(str::to_string),
+                phase,
+                timestamp,
+                thread: THREAD.try_with(|thread| *thread).unwrap_or_default(),
+            });
+        }
+    }
+}
+
+/// Write events in the Chrome trace event JSON format
+fn write_events(path: &Path, events: &[Event]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let pid = process::id();
+    write!(file, "{{\"traceEvents\":[")?;
+    for (i, event) in events.iter().enumerate() {
+        if i > 0 {
+            write!(file, ",")?;
+        }
+        write!(
+            file,
+            "\n{{\"name\":{},\"cat\":{},\"ph\":\"{}\",\"ts\":{},\"pid\":{},\"tid\":{}}}",
+            json_string(&event.name),
+            json_string(event.category.as_deref().unwrap_or("behold")),
+            event.phase,
+            event.timestamp,
+            pid,
+            event.thread
+        )?;
+    }
+    writeln!(file, "\n]}}")?;
+    file.flush()
+}
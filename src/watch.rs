@@ -0,0 +1,69 @@
+//! Tracking of the values watched by `behold_watch!` and `show_if_changed`, so that they're
+//! only shown on change.
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use Location;
+
+/// A watched expression and where it's watched from
+#[derive(PartialEq, Eq, Hash)]
+struct Watched {
+    location: Option<(&'static str, u32)>,
+    expression: String,
+}
+
+lazy_static! {
+    /// The last `Debug` representation of each watched expression
+    static ref LAST_SEEN: Mutex<HashMap<Watched, String>> = Mutex::new(HashMap::new());
+    /// The last value shown by `show_if_changed_by` for each label
+    static ref LAST_VALUES: Mutex<HashMap<Watched, Box<dyn Any + Send>>> = Mutex::new(HashMap::new());
+}
+
+/// Record the latest representation of an expression, returning true if it changed
+pub(crate) fn changed(location: Option<&Location>, expression: &str, rendered: &str) -> bool {
+    let watched = Watched {
+        location: location.map(|location| (location.file, location.line)),
+        expression: expression.to_string(),
+    };
+    match LAST_SEEN.lock() {
+        Ok(mut last_seen) => match last_seen.get(&watched) {
+            Some(last) if last == rendered => false,
+            _ => {
+                last_seen.insert(watched, rendered.to_string());
+                true
+            }
+        },
+        Err(_) => true,
+    }
+}
+
+/// Record the latest value for a label, returning true if it differs from the last one
+/// according to `eq` - or if there is no last one of the same type
+pub(crate) fn changed_by<T, F>(location: Option<&Location>, label: &str, value: &T, eq: F) -> bool
+where
+    T: Clone + Send + 'static,
+    F: Fn(&T, &T) -> bool,
+{
+    let watched = Watched {
+        location: location.map(|location| (location.file, location.line)),
+        expression: label.to_string(),
+    };
+    // Compare without the lock held, in case the comparison shows output of its own
+    let last = match LAST_VALUES.lock() {
+        Ok(mut last_values) => last_values.remove(&watched),
+        Err(_) => return true,
+    };
+    let changed = match last.as_ref().and_then(|last| last.downcast_ref::<T>()) {
+        Some(last) => !eq(last, value),
+        None => true,
+    };
+    let latest: Box<dyn Any + Send> = match last {
+        Some(last) if !changed => last,
+        _ => Box::new(value.clone()),
+    };
+    if let Ok(mut last_values) = LAST_VALUES.lock() {
+        last_values.insert(watched, latest);
+    }
+    changed
+}